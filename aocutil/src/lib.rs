@@ -3,6 +3,7 @@ use std::fs;
 pub mod grid;
 pub mod direction;
 pub mod coord;
+pub mod math;
 
 pub fn load_test_input(day: u8) -> String {
     load_file(&format!("day{day}/test_input.txt"))
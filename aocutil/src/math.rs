@@ -0,0 +1,175 @@
+/// The greatest common divisor, which is never negative. `gcd(0, 0)` is 0.
+pub fn gcd(a: i64, b: i64) -> i64 {
+    extended_gcd(a, b).0
+}
+
+/// The least common multiple, which is never negative. Panics if it overflows.
+pub fn lcm(a: i64, b: i64) -> i64 {
+    checked_lcm(a, b).unwrap_or_else(|| panic!("lcm({a}, {b}) overflows"))
+}
+
+pub fn checked_lcm(a: i64, b: i64) -> Option<i64> {
+    if a == 0 || b == 0 {
+        return Some(0);
+    }
+    (a / gcd(a, b)).checked_mul(b)?.checked_abs()
+}
+
+/// Returns `(g, x, y)` such that `a * x + b * y = g`, where `g = gcd(a, b)`. Panics if `g` is
+/// `2^63`, which only happens when each of `a` and `b` is `0` or `i64::MIN` and at least one is
+/// `i64::MIN`.
+pub fn extended_gcd(a: i64, b: i64) -> (i64, i64, i64) {
+    let (g, x, y) = extended_gcd_wide(a as i128, b as i128);
+    let g = i64::try_from(g).unwrap_or_else(|_| panic!("gcd({a}, {b}) overflows"));
+    (g, x as i64, y as i64)
+}
+
+/// Works in `i128` so that no intermediate values overflow.
+fn extended_gcd_wide(a: i128, b: i128) -> (i128, i128, i128) {
+    let (mut r0, mut r1) = (a, b);
+    let (mut x0, mut x1) = (1, 0);
+    let (mut y0, mut y1) = (0, 1);
+    while r1 != 0 {
+        let q = r0 / r1;
+        (r0, r1) = (r1, r0 - q * r1);
+        (x0, x1) = (x1, x0 - q * x1);
+        (y0, y1) = (y1, y0 - q * y1);
+    }
+    if r0 < 0 { (-r0, -x0, -y0) } else { (r0, x0, y0) }
+}
+
+/// The `x` in `0..m` with `a * x = 1 (mod m)`, or `None` if `a` and `m` are not coprime.
+pub fn mod_inverse(a: i64, m: i64) -> Option<i64> {
+    assert!(m > 0, "Modulus must be positive");
+    let (g, x, _) = extended_gcd_wide(a as i128, m as i128);
+    (g == 1).then(|| x.rem_euclid(m as i128) as i64)
+}
+
+/// Solves the congruences `x = r (mod m)` for each `(r, m)`, returning `(x, lcm of the moduli)`
+/// with `x` the smallest non-negative solution, or `None` if they are inconsistent. The moduli need
+/// not be coprime. Panics if the combined modulus overflows.
+pub fn crt(congruences: &[(i64, i64)]) -> Option<(i64, i64)> {
+    checked_crt(congruences).unwrap_or_else(|Overflow| panic!("Combined modulus of {congruences:?} overflows"))
+}
+
+/// The error from a checked calculation whose result doesn't fit in an `i64`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Overflow;
+
+/// As [`crt`], but returns an error rather than panicking if the combined modulus overflows.
+pub fn checked_crt(congruences: &[(i64, i64)]) -> Result<Option<(i64, i64)>, Overflow> {
+    let (mut x, mut m): (i128, i128) = (0, 1);
+    for &(r, n) in congruences {
+        assert!(n > 0, "Modulus must be positive");
+        let (r, n) = ((r as i128).rem_euclid(n as i128), n as i128);
+        let (g, p, _) = extended_gcd_wide(m, n);
+        if (r - x) % g != 0 {
+            return Ok(None);
+        }
+        // x + m * k = r (mod n), so k = (r - x) / g * inverse of m / g (mod n / g)
+        let step = n / g;
+        let k = ((r - x) / g % step * p).rem_euclid(step);
+        m *= step;
+        if m > i64::MAX as i128 {
+            return Err(Overflow);
+        }
+        x = (x + m / step * k).rem_euclid(m);
+    }
+    Ok(Some((x as i64, m as i64)))
+}
+
+/// The number of decimal digits in `n`, counting zero as one digit.
+pub fn digit_count(n: u64) -> u32 {
+    n.checked_ilog10().unwrap_or(0) + 1
+}
+
+/// Splits `n` into the digits before its last `right` digits, and the last `right` digits.
+pub fn split_digits(n: u64, right: u32) -> (u64, u64) {
+    if right >= digit_count(n) {
+        return (0, n);
+    }
+    let shift = 10u64.pow(right);
+    (n / shift, n % shift)
+}
+
+/// Appends the digits of `right` to `left`, e.g. `concat_digits(12, 0) = 120`. Panics if the result
+/// overflows.
+pub fn concat_digits(left: u64, right: u64) -> u64 {
+    checked_concat_digits(left, right).unwrap_or_else(|| panic!("Concatenating {left} and {right} overflows"))
+}
+
+pub fn checked_concat_digits(left: u64, right: u64) -> Option<u64> {
+    left.checked_mul(10u64.checked_pow(digit_count(right))?)?.checked_add(right)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_gcd_and_lcm() {
+        assert_eq!(gcd(12, 18), 6);
+        assert_eq!(gcd(-12, 18), 6);
+        assert_eq!(gcd(0, 5), 5);
+        assert_eq!(gcd(0, 0), 0);
+        assert_eq!(gcd(i64::MIN, i64::MIN + 1), 1);
+        assert_eq!(lcm(4, 6), 12);
+        assert_eq!(lcm(-4, 6), 12);
+        assert_eq!(lcm(0, 6), 0);
+        assert_eq!(checked_lcm(i64::MAX, i64::MAX - 1), None);
+    }
+
+    #[test]
+    fn finds_bezout_coefficients() {
+        for (a, b) in [(240, 46), (-240, 46), (0, 7), (7, 0), (17, 17), (1 << 40, 3 << 20)] {
+            let (g, x, y) = extended_gcd(a, b);
+            assert_eq!(g, gcd(a, b));
+            assert_eq!(a * x + b * y, g, "{a} {b}");
+        }
+    }
+
+    #[test]
+    #[should_panic(expected = "overflows")]
+    fn rejects_gcd_of_minimum_and_zero() {
+        extended_gcd(i64::MIN, 0);
+    }
+
+    #[test]
+    fn finds_modular_inverses() {
+        assert_eq!(mod_inverse(3, 7), Some(5));
+        assert_eq!(mod_inverse(-3, 7), Some(2));
+        assert_eq!(mod_inverse(101, 103), Some(51));
+        assert_eq!(mod_inverse(4, 8), None);
+        assert_eq!(mod_inverse(5, 1), Some(0));
+    }
+
+    #[test]
+    fn solves_congruences() {
+        assert_eq!(crt(&[(2, 3), (3, 5), (2, 7)]), Some((23, 105)));
+        assert_eq!(crt(&[(-1, 101), (5, 103)]), Some((10099, 10403)));
+        assert_eq!(crt(&[(3, 4), (5, 6)]), Some((11, 12)));
+        assert_eq!(crt(&[(1, 4), (2, 6)]), None);
+        assert_eq!(crt(&[]), Some((0, 1)));
+        assert_eq!(checked_crt(&[(0, i64::MAX), (1, 2)]), Err(Overflow));
+        assert_eq!(checked_crt(&[(0, i64::MAX), (1, i64::MAX)]), Ok(None));
+        let big = (1 << 61) - 1;
+        assert_eq!(crt(&[(big - 1, big), (1, 2)]), Some((big - 1 + big, 2 * big)));
+    }
+
+    #[test]
+    fn handles_digits() {
+        assert_eq!(digit_count(0), 1);
+        assert_eq!(digit_count(9), 1);
+        assert_eq!(digit_count(10), 2);
+        assert_eq!(digit_count(u64::MAX), 20);
+        assert_eq!(split_digits(253000, 3), (253, 0));
+        assert_eq!(split_digits(1000, 2), (10, 0));
+        assert_eq!(split_digits(42, 5), (0, 42));
+        assert_eq!(split_digits(u64::MAX, 19), (1, u64::MAX - 10u64.pow(19)));
+        assert_eq!(concat_digits(12, 345), 12345);
+        assert_eq!(concat_digits(12, 0), 120);
+        assert_eq!(concat_digits(0, 7), 7);
+        assert_eq!(checked_concat_digits(u64::MAX / 10, 9), None);
+        assert_eq!(checked_concat_digits(1, 10u64.pow(19)), None);
+    }
+}
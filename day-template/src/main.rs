@@ -12,6 +12,7 @@ fn main() {
     println!("Part 2: {part2}");
 }
 
+#[allow(unused_variables)]
 fn parse_input(input: &str) {
     // let re = Regex::new(r"^(\d+) +(\d+)$").unwrap();
     // for line in input.lines() {
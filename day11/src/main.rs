@@ -1,51 +1,46 @@
-use std::collections::HashMap;
+use std::env;
 
-const DAY: u8 = 11;
+use num_bigint::BigUint;
 
-fn main() {
-    let input = aocutil::load_input(DAY);
-    let numbers = parse_input(&input);
+use stones::{standard_rules, Rule, Stones};
 
-    let mut cache: HashMap<(u64, u32), usize> = HashMap::new();
-    println!("Part 1: {}", length_after_iterations(&numbers, 25, &mut cache));
-    println!("Part 2: {}", length_after_iterations(&numbers, 75, &mut cache));
-}
+mod stones;
 
-fn length_after_iterations(values: &[u64], iterations: u32, cache: &mut HashMap<(u64, u32), usize>) -> usize {
-    values.iter()
-        .map(|&value| length_after_iterations1(value, iterations, cache))
-        .sum()
-}
+const DAY: u8 = 11;
 
-fn length_after_iterations1(value: u64, iterations: u32, cache: &mut HashMap<(u64, u32), usize>) -> usize {
-    if iterations == 0 {
-        1
-    } else {
-        match cache.get(&(value, iterations)) {
-            Some(&length) => length,
-            None => {
-                let length = length_after_iterations(&expand(value), iterations - 1, cache);
-                cache.insert((value, iterations), length);
-                length
-            }
-        }
-    }
-}
+/// Usage: `day11 [report BLINKS [RULE...]]`
+///
+/// `report` prints the number of stones and distinct values after each blink, followed by the
+/// histogram of values in the final generation. Rules default to the puzzle's rules and are
+/// written as `N -> A B ...`, `split` or `* N`, e.g. `day11 report 10 "0 -> 1" split "* 4048"`.
+fn main() {
+    let input = aocutil::load_input(DAY);
+    let stones = Stones::new(&parse_input(&input));
+    let args: Vec<String> = env::args().skip(1).collect();
 
-fn expand(value: u64) -> Vec<u64> {
-    if value == 0 {
-        vec![1]
-    } else {
-        let digits = value.ilog10() + 1;
-        if digits % 2 == 0 {
-            let split = 10u64.pow(digits / 2);
-            vec![value / split, value % split]
+    if let [command, blinks, rules @ ..] = &args[..] {
+        assert_eq!(command, "report", "Unknown command '{command}'");
+        let blinks = blinks.parse().unwrap_or_else(|_| panic!("Invalid number of blinks '{blinks}'"));
+        let rules = if rules.is_empty() {
+            standard_rules()
         } else {
-            vec![value * 2024]
+            rules.iter().map(|r| Rule::parse(r).unwrap_or_else(|| panic!("Invalid rule '{r}'"))).collect()
+        };
+        let generations = stones.generations(&rules, blinks);
+        for (blink, generation) in generations.iter().enumerate() {
+            println!("{blink}: {} stones, {} distinct", generation.total(), generation.distinct());
         }
+        for (value, count) in generations.last().unwrap().histogram() {
+            println!("{value}: {count}");
+        }
+        return;
     }
+
+    let generations = stones.generations(&standard_rules(), 75);
+    println!("Part 1: {}", generations[25].total());
+    println!("Part 2: {}", generations[75].total());
 }
 
-fn parse_input(input: &str) -> Vec<u64> {
-    input.split_whitespace().map(|n| n.parse::<u64>().unwrap()).collect()
-}
\ No newline at end of file
+fn parse_input(input: &str) -> Vec<BigUint> {
+    input.split_whitespace().map(|n| n.parse::<BigUint>().unwrap()).collect()
+}
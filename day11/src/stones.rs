@@ -0,0 +1,161 @@
+use std::collections::BTreeMap;
+
+use num_bigint::BigUint;
+
+/// Replaces one stone with the stones it turns into, or `None` if the rule does not apply.
+pub type RuleFn = dyn Fn(&BigUint) -> Option<Vec<BigUint>>;
+
+pub enum Rule {
+    /// A stone with exactly this value is replaced by the given stones
+    Replace(BigUint, Vec<BigUint>),
+    /// A stone with an even number of digits splits into its left and right halves
+    SplitEvenDigits,
+    /// A stone is multiplied by a constant
+    Multiply(BigUint),
+    /// Not used by the puzzle's rules, which can all be written as data
+    #[allow(dead_code)]
+    Custom(Box<RuleFn>),
+}
+
+impl Rule {
+    /// Parses a rule written as `N -> A B ...`, `split` or `* N`.
+    pub fn parse(rule: &str) -> Option<Rule> {
+        let rule = rule.trim();
+        if rule == "split" {
+            return Some(Rule::SplitEvenDigits);
+        }
+        if let Some(factor) = rule.strip_prefix('*') {
+            return Some(Rule::Multiply(factor.trim().parse().ok()?));
+        }
+        let (value, replacements) = rule.split_once("->")?;
+        let replacements = replacements.split_whitespace()
+            .map(|r| r.parse().ok())
+            .collect::<Option<Vec<BigUint>>>()?;
+        Some(Rule::Replace(value.trim().parse().ok()?, replacements))
+    }
+
+    fn apply(&self, value: &BigUint) -> Option<Vec<BigUint>> {
+        match self {
+            Rule::Replace(from, to) => (value == from).then(|| to.clone()),
+            Rule::SplitEvenDigits => {
+                let digits = value.to_string();
+                if !digits.len().is_multiple_of(2) {
+                    return None;
+                }
+                let (left, right) = digits.split_at(digits.len() / 2);
+                Some(vec![left.parse().unwrap(), right.parse().unwrap()])
+            }
+            Rule::Multiply(factor) => Some(vec![value * factor]),
+            Rule::Custom(f) => f(value),
+        }
+    }
+}
+
+/// The puzzle's rules, in the order they are checked.
+pub fn standard_rules() -> Vec<Rule> {
+    vec![
+        Rule::Replace(BigUint::ZERO, vec![BigUint::from(1u32)]),
+        Rule::SplitEvenDigits,
+        Rule::Multiply(BigUint::from(2024u32)),
+    ]
+}
+
+/// A multiset of stones: the number of stones with each value. Stones are independent of their
+/// neighbours, so only the count of each distinct value is needed to work out the next generation.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Stones {
+    counts: BTreeMap<BigUint, BigUint>,
+}
+
+impl Stones {
+    pub fn new(values: &[BigUint]) -> Stones {
+        let mut counts = BTreeMap::new();
+        for value in values {
+            *counts.entry(value.clone()).or_insert(BigUint::ZERO) += 1u32;
+        }
+        Stones { counts }
+    }
+
+    /// Applies the first matching rule to every stone. A stone that no rule matches is unchanged.
+    pub fn blink(&self, rules: &[Rule]) -> Stones {
+        let mut counts = BTreeMap::new();
+        for (value, count) in &self.counts {
+            let replacements = rules.iter()
+                .find_map(|rule| rule.apply(value))
+                .unwrap_or_else(|| vec![value.clone()]);
+            for replacement in replacements {
+                *counts.entry(replacement).or_insert(BigUint::ZERO) += count;
+            }
+        }
+        Stones { counts }
+    }
+
+    /// Returns every generation from this one up to and including `blinks` blinks later.
+    pub fn generations(&self, rules: &[Rule], blinks: usize) -> Vec<Stones> {
+        let mut generations = vec![self.clone()];
+        for _ in 0..blinks {
+            generations.push(generations.last().unwrap().blink(rules));
+        }
+        generations
+    }
+
+    pub fn total(&self) -> BigUint {
+        self.counts.values().sum()
+    }
+
+    pub fn distinct(&self) -> usize {
+        self.counts.len()
+    }
+
+    /// The number of stones with each value, in order of value.
+    pub fn histogram(&self) -> &BTreeMap<BigUint, BigUint> {
+        &self.counts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stones(values: &[u64]) -> Stones {
+        Stones::new(&values.iter().map(|&v| BigUint::from(v)).collect::<Vec<_>>())
+    }
+
+    #[test]
+    fn blinks_example() {
+        let generations = stones(&[125, 17]).generations(&standard_rules(), 25);
+        assert_eq!(generations[1], stones(&[253000, 1, 7]));
+        assert_eq!(generations[6], stones(&[
+            2097446912, 14168, 4048, 2, 0, 2, 4, 40, 48, 2024, 40, 48, 80, 96, 2, 8, 6, 7, 6, 0, 3, 2
+        ]));
+        assert_eq!(generations[6].total(), BigUint::from(22u32));
+        assert_eq!(generations[6].distinct(), 15);
+        assert_eq!(generations[6].histogram()[&BigUint::from(2u32)], BigUint::from(4u32));
+        assert_eq!(generations[25].total(), BigUint::from(55312u32));
+    }
+
+    #[test]
+    fn parses_rules() {
+        let rules: Vec<Rule> = ["0 -> 1", "split", "* 2024"].iter().map(|r| Rule::parse(r).unwrap()).collect();
+        assert_eq!(stones(&[125, 17]).generations(&rules, 25)[25].total(), BigUint::from(55312u32));
+        assert!(Rule::parse("0 ->").is_some());
+        assert!(Rule::parse("* x").is_none());
+    }
+
+    #[test]
+    fn supports_closures_and_large_values() {
+        let rules = vec![
+            Rule::Custom(Box::new(|v| (v % 2u32 == BigUint::ZERO).then(|| vec![v / 2u32, v / 2u32]))),
+            Rule::Multiply("1000000000000000000000".parse().unwrap()),
+        ];
+        let generations = stones(&[1]).generations(&rules, 3);
+        assert_eq!(generations[3].total(), BigUint::from(4u32));
+        assert_eq!(generations[3].histogram().keys().next().unwrap().to_string(), "250000000000000000000");
+    }
+
+    #[test]
+    fn counts_beyond_u64() {
+        let total = stones(&[0]).generations(&standard_rules(), 200)[200].total();
+        assert!(total > BigUint::from(u64::MAX));
+    }
+}
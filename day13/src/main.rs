@@ -1,54 +1,99 @@
 use regex::Regex;
 
 use aocutil::coord::Coord;
+use aocutil::math::extended_gcd;
 
 const DAY: u8 = 13;
 
+/// The tokens it costs to press buttons A and B.
+const COSTS: (i128, i128) = (3, 1);
+
 fn main() {
     let input = aocutil::load_input(DAY);
     let games = parse_input(&input);
 
     let part1: i64 = games.iter()
-        .filter_map(|&(a, b, t)| min_score1(a, b, t))
+        .filter_map(|&(a, b, t)| min_tokens(a, b, t, Some(100)))
         .sum();
     println!("Part 1: {part1}");
 
     let part2: i64 = games.iter()
-        .filter_map(|&(a, b, (tx, ty))| min_score2(a, b, (tx + 10000000000000, ty + 10000000000000)))
+        .filter_map(|&(a, b, (tx, ty))| min_tokens(a, b, (tx + 10000000000000, ty + 10000000000000), None))
         .sum();
     println!("Part 2: {part2}");
 }
 
-fn min_score1((ax, ay): Coord, (bx, by): Coord, (tx, ty): Coord) -> Option<i64> {
-    (0..=100)
-        .flat_map(|b| (0..=100).map(|a| (a, b)).collect::<Vec<(i64, i64)>>())
-        .filter(|(a, b)| (ax * a + bx * b, ay * a + by * b) == (tx, ty))
-        .map(|(a, b)| 3 * a + b)
-        .min()
+/// The fewest tokens needed to reach the prize, pressing each button no more than `limit` times.
+fn min_tokens(a: Coord, b: Coord, target: Coord, limit: Option<i64>) -> Option<i64> {
+    let (a, b) = min_presses(a, b, target, limit)?;
+    Some((COSTS.0 * a as i128 + COSTS.1 * b as i128) as i64)
 }
 
-fn min_score2((ax, ay): Coord, (bx, by): Coord, (tx, ty): Coord) -> Option<i64> {
-
-    // We don't handle the case where the lines have the same gradient (YAGNI)
-    // Modular arithmetic may be required to find the best solution in this case?
-    assert_ne!(ay * bx, ax * by);
-
-    // Solve equations of lines to find x coordinate of intersection I
-    // This should be a precise integer for actual solutions, otherwise rounded junk
-    let ix = ax * (bx * ty - by * tx) / (ay * bx - ax * by);
+/// Solves `a * (ax, ay) + b * (bx, by) = (tx, ty)` for the non-negative integer numbers of presses
+/// `a` and `b` that cost the fewest tokens. Arithmetic is done in `i128` so that no products overflow.
+fn min_presses((ax, ay): Coord, (bx, by): Coord, (tx, ty): Coord, limit: Option<i64>) -> Option<(i64, i64)> {
+    let (ax, ay, bx, by, tx, ty) = (ax as i128, ay as i128, bx as i128, by as i128, tx as i128, ty as i128);
+    let limit = limit.map(|limit| limit as i128);
 
-    // Find integer approximations of a and b (numbers of button presses)
-    let a = ix / ax;
-    let b = (tx - ix) / bx;
-
-    // Confirm that a and b integer solutions are correct, otherwise not possible
-    if (ax * a + bx * b, ay * a + by * b) == (tx, ty) {
-        Some(3 * a + b)
+    let det = ax * by - ay * bx;
+    let (a, b) = if det != 0 {
+        // The buttons move in different directions, so by Cramer's rule there is at most one solution
+        let (a, b) = (tx * by - ty * bx, ax * ty - ay * tx);
+        if a % det != 0 || b % det != 0 {
+            return None;
+        }
+        let (a, b) = (a / det, b / det);
+        let in_range = |n: i128| n >= 0 && limit.is_none_or(|limit| n <= limit);
+        (in_range(a) && in_range(b)).then_some((a, b))?
+    } else if (ax, bx) != (0, 0) {
+        // The rows are multiples of each other, so any solution to one equation solves the other if
+        // any solution does
+        collinear_presses(ax, bx, tx, limit)?
     } else {
-        None
+        collinear_presses(ay, by, ty, limit)?
+    };
+    ((ax * a + bx * b, ay * a + by * b) == (tx, ty)).then_some((a as i64, b as i64))
+}
+
+/// Minimises the cost of `p * a + q * b = t` over non-negative integers. Every solution is
+/// `a = a0 + k * q / g, b = b0 - k * p / g` for a particular solution found with the extended
+/// Euclidean algorithm, where `g = gcd(p, q)`. The cost is linear in `k`, so is least at one end
+/// of the range of `k` that keeps both presses in range.
+fn collinear_presses(p: i128, q: i128, t: i128, limit: Option<i128>) -> Option<(i128, i128)> {
+    if (p, q) == (0, 0) {
+        return (t == 0).then_some((0, 0));
     }
+    // The coefficients come from the buttons' moves, so fit in an i64
+    let (g, x, y) = extended_gcd(p as i64, q as i64);
+    let (g, x, y) = (g as i128, x as i128, y as i128);
+    if t % g != 0 {
+        return None;
+    }
+    let (a0, b0, da, db) = (x * (t / g), y * (t / g), q / g, -p / g);
+    let (mut low, mut high) = (i128::MIN, i128::MAX);
+    for (n0, dn) in [(a0, da), (b0, db)] {
+        let (low_k, high_k) = steps_in_range(n0, dn, limit)?;
+        (low, high) = (low.max(low_k), high.min(high_k));
+    }
+    if low > high {
+        return None;
+    }
+    // Both costs are positive, so the cheaper direction always decreases a press that is bounded
+    // below by zero, and the chosen end of the range is finite
+    let k = if COSTS.0 * da + COSTS.1 * db > 0 { low } else { high };
+    Some((a0 + k * da, b0 + k * db))
 }
 
+/// The range of `k` for which `0 <= n0 + k * dn <= limit`, or `None` if there is none.
+fn steps_in_range(n0: i128, dn: i128, limit: Option<i128>) -> Option<(i128, i128)> {
+    let floor_div = |n: i128, d: i128| n.div_euclid(d);
+    let ceil_div = |n: i128, d: i128| -(-n).div_euclid(d);
+    match dn.signum() {
+        0 => (n0 >= 0 && limit.is_none_or(|limit| n0 <= limit)).then_some((i128::MIN, i128::MAX)),
+        1 => Some((ceil_div(-n0, dn), limit.map_or(i128::MAX, |limit| floor_div(limit - n0, dn)))),
+        _ => Some((limit.map_or(i128::MIN, |limit| ceil_div(n0 - limit, -dn)), floor_div(n0, -dn))),
+    }
+}
 
 fn parse_input(input: &str) -> Vec<(Coord, Coord, Coord)> {
     let re = Regex::new(r"Button A: X\+(\d+), Y\+(\d+)
@@ -60,4 +105,59 @@ Prize: X=(\d+), Y=(\d+)").unwrap();
         result.push(((n[0], n[1]), (n[2], n[3]), (n[4], n[5])))
     }
     result
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    /// The original part 1 solution, trying every number of presses up to the limit.
+    fn brute_force((ax, ay): Coord, (bx, by): Coord, (tx, ty): Coord, limit: i64) -> Option<i64> {
+        (0..=limit)
+            .flat_map(|b| (0..=limit).map(move |a| (a, b)))
+            .filter(|(a, b)| (ax * a + bx * b, ay * a + by * b) == (tx, ty))
+            .map(|(a, b)| 3 * a + b)
+            .min()
+    }
+
+    #[test]
+    fn solves_example() {
+        let games = parse_input(&fs::read_to_string("test_input.txt").unwrap());
+        let tokens: Vec<Option<i64>> = games.iter().map(|&(a, b, t)| min_tokens(a, b, t, Some(100))).collect();
+        assert_eq!(tokens, vec![Some(280), None, Some(200), None]);
+        let solvable: Vec<bool> = games.iter()
+            .map(|&(a, b, (tx, ty))| min_tokens(a, b, (tx + 10000000000000, ty + 10000000000000), None).is_some())
+            .collect();
+        assert_eq!(solvable, vec![false, true, false, true]);
+    }
+
+    #[test]
+    fn solves_collinear_buttons() {
+        assert_eq!(min_presses((1, 1), (3, 3), (6, 6), None), Some((0, 2)));
+        assert_eq!(min_presses((2, 2), (3, 3), (7, 7), None), Some((2, 1)));
+        assert_eq!(min_presses((1, 1), (1, 1), (150, 150), Some(100)), Some((50, 100)));
+        assert_eq!(min_presses((1, 1), (1, 1), (250, 250), Some(100)), None);
+        assert_eq!(min_presses((1, 1), (2, 2), (3, 4), None), None);
+        assert_eq!(min_presses((4, 6), (6, 9), (5, 7), None), None);
+        assert_eq!(min_presses((0, 0), (1, 2), (3, 6), None), Some((0, 3)));
+        assert_eq!(min_presses((0, 0), (0, 0), (0, 0), None), Some((0, 0)));
+        assert_eq!(min_presses((0, 3), (0, 5), (0, 11), None), Some((2, 1)));
+        // A is cheaper per unit of distance only when it moves more than three times as far as B
+        assert_eq!(min_presses((1, 1), (4, 4), (12, 12), None), Some((0, 3)));
+        assert_eq!(min_presses((5, 5), (1, 1), (12, 12), None), Some((2, 2)));
+    }
+
+    #[test]
+    fn matches_brute_force() {
+        let vectors = [(0, 0), (1, 0), (0, 1), (1, 1), (2, 2), (3, 1), (1, 3), (4, 6), (6, 9), (5, 2)];
+        for a in vectors {
+            for b in vectors {
+                for target in [(0, 0), (6, 6), (12, 18), (13, 9), (7, 3), (30, 30), (20, 30)] {
+                    assert_eq!(min_tokens(a, b, target, Some(10)), brute_force(a, b, target, 10), "{a:?} {b:?} {target:?}");
+                }
+            }
+        }
+    }
+}
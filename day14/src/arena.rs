@@ -0,0 +1,222 @@
+use std::collections::HashMap;
+
+use regex::Regex;
+
+use aocutil::coord::Coord;
+use aocutil::grid::Grid;
+use aocutil::math::{crt, lcm};
+
+use crate::metric::Metric;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Robot {
+    pub position: Coord,
+    pub velocity: Coord,
+}
+
+/// Robots moving in straight lines across an arena whose edges wrap around.
+#[derive(Clone, Debug, PartialEq)]
+pub struct RobotArena {
+    width: i64,
+    height: i64,
+    robots: Vec<Robot>,
+}
+
+impl RobotArena {
+    pub fn new(width: i64, height: i64, robots: Vec<Robot>) -> RobotArena {
+        assert!(width > 0 && height > 0, "Arena must not be empty");
+        let robots = robots.into_iter()
+            .map(|robot| Robot { position: (robot.position.0.rem_euclid(width), robot.position.1.rem_euclid(height)), ..robot })
+            .collect();
+        RobotArena { width, height, robots }
+    }
+
+    /// Parses one `p=X,Y v=DX,DY` robot per line.
+    pub fn parse(input: &str, width: i64, height: i64) -> RobotArena {
+        let re = Regex::new(r"^p=(.+),(.+) +v=(.+),(.+)$").unwrap();
+        let robots = input.lines()
+            .map(|line| {
+                let (_, values) = re.captures(line).unwrap_or_else(|| panic!("Invalid robot '{line}'")).extract::<4>();
+                let n: Vec<i64> = values.iter().map(|s| s.parse().unwrap()).collect();
+                Robot { position: (n[0], n[1]), velocity: (n[2], n[3]) }
+            })
+            .collect();
+        RobotArena::new(width, height, robots)
+    }
+
+    pub fn positions(&self) -> Vec<Coord> {
+        self.robots.iter().map(|robot| robot.position).collect()
+    }
+
+    pub fn step(&mut self) {
+        *self = self.after(1);
+    }
+
+    /// The arena as it will be after `ticks` ticks, which may be negative to go back in time.
+    pub fn after(&self, ticks: i64) -> RobotArena {
+        let robots = self.robots.iter()
+            .map(|&Robot { position: (x, y), velocity: (dx, dy) }| Robot {
+                position: ((x + dx * ticks).rem_euclid(self.width), (y + dy * ticks).rem_euclid(self.height)),
+                velocity: (dx, dy),
+            })
+            .collect();
+        RobotArena { robots, ..*self }
+    }
+
+    /// The number of ticks after which every robot is back where it started.
+    pub fn period(&self) -> i64 {
+        lcm(self.width, self.height)
+    }
+
+    /// The number of robots in the top left, top right, bottom left and bottom right quadrants.
+    /// Robots on the middle row or column of an odd sized arena are in no quadrant, and an even
+    /// sized arena splits exactly in half.
+    pub fn quadrant_counts(&self) -> [usize; 4] {
+        let mut counts = [0; 4];
+        for &Robot { position: (x, y), .. } in &self.robots {
+            let column = half(x, self.width);
+            let row = half(y, self.height);
+            if let (Some(column), Some(row)) = (column, row) {
+                counts[row * 2 + column] += 1;
+            }
+        }
+        counts
+    }
+
+    pub fn safety_factor(&self) -> usize {
+        self.quadrant_counts().iter().product()
+    }
+
+    /// Draws the arena with the number of robots on each cell, as in the puzzle description.
+    pub fn to_grid(&self) -> Grid {
+        let mut counts: HashMap<Coord, u32> = HashMap::new();
+        for robot in &self.robots {
+            *counts.entry(robot.position).or_insert(0) += 1;
+        }
+        let mut grid = Grid::new();
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let cell = counts.get(&(x, y)).map_or('.', |&count| char::from_digit(count, 10).unwrap_or('+'));
+                grid.set((x, y), cell);
+            }
+        }
+        grid
+    }
+
+    /// How disordered the robots are now, by the given metric.
+    pub fn disorder(&self, metric: &dyn Metric) -> f64 {
+        metric.disorder(&self.positions())
+    }
+
+    /// Finds the tick at which the robots are most ordered. The robots' x coordinates repeat every
+    /// `width` ticks and their y coordinates every `height` ticks, so if the metric can score each
+    /// axis on its own, the most ordered tick for each axis is found separately and the two are
+    /// combined with the Chinese Remainder Theorem. Otherwise, or if the combination is ambiguous
+    /// because the width and height share a factor, every arrangement is scored.
+    pub fn find_tree(&self, metric: &dyn Metric) -> i64 {
+        let x = least_disordered((0..self.width).map(|ticks| metric.axis_disorder(&self.axis_after(ticks, |c| c.0))));
+        let y = least_disordered((0..self.height).map(|ticks| metric.axis_disorder(&self.axis_after(ticks, |c| c.1))));
+        let combined = match (x, y) {
+            (Some(x), Some(y)) if lcm(self.width, self.height) == self.width * self.height => crt(&[(x, self.width), (y, self.height)]),
+            _ => None,
+        };
+        combined.map_or_else(
+            || {
+                let mut arena = self.clone();
+                least_disordered((0..self.period()).map(|_| {
+                    let score = arena.disorder(metric);
+                    arena.step();
+                    Some(score)
+                })).unwrap()
+            },
+            |(ticks, _)| ticks,
+        )
+    }
+
+    fn axis_after(&self, ticks: i64, axis: fn(Coord) -> i64) -> Vec<i64> {
+        self.after(ticks).robots.iter().map(|robot| axis(robot.position)).collect()
+    }
+}
+
+/// Which half of a row or column of `size` cells a coordinate is in, or `None` if it is in the middle.
+fn half(n: i64, size: i64) -> Option<usize> {
+    if size % 2 == 1 && n == size / 2 {
+        None
+    } else {
+        Some((n >= size / 2) as usize)
+    }
+}
+
+/// The first tick with the lowest score, given the score at each tick from zero, or `None` if
+/// any tick cannot be scored.
+fn least_disordered(scores: impl Iterator<Item = Option<f64>>) -> Option<i64> {
+    scores.collect::<Option<Vec<f64>>>()?
+        .into_iter()
+        .zip(0..)
+        .min_by(|(a, _), (b, _)| a.total_cmp(b))
+        .map(|(_, ticks)| ticks)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::metric::METRICS;
+
+    #[test]
+    fn simulates_example() {
+        let arena = RobotArena::parse(&fs::read_to_string("test_input.txt").unwrap(), 11, 7);
+        assert_eq!(arena.after(100).quadrant_counts(), [1, 3, 4, 1]);
+        assert_eq!(arena.after(100).safety_factor(), 12);
+        assert_eq!(arena.period(), 77);
+        assert_eq!(arena.after(arena.period()), arena);
+
+        let mut robot = RobotArena::new(11, 7, vec![Robot { position: (2, 4), velocity: (2, -3) }]);
+        let mut positions = vec![];
+        for _ in 0..5 {
+            robot.step();
+            positions.push(robot.positions()[0]);
+        }
+        assert_eq!(positions, vec![(4, 1), (6, 5), (8, 2), (10, 6), (1, 3)]);
+        assert_eq!(robot.after(-5).positions(), vec![(2, 4)]);
+        assert_eq!(format!("{:?}", robot.to_grid()).lines().nth(3), Some(".1........."));
+    }
+
+    #[test]
+    fn splits_even_arenas_in_half() {
+        let robots = [(0, 0), (1, 0), (2, 0), (3, 3), (2, 2), (1, 2)]
+            .map(|position| Robot { position, velocity: (0, 0) })
+            .to_vec();
+        assert_eq!(RobotArena::new(4, 4, robots.clone()).quadrant_counts(), [2, 1, 1, 2]);
+        assert_eq!(RobotArena::new(5, 5, robots).quadrant_counts(), [2, 0, 0, 1]);
+    }
+
+    /// Robots that form a solid block at `tick` and scatter with pseudo-random velocities otherwise.
+    fn block_at(tick: i64, width: i64, height: i64) -> RobotArena {
+        let mut seed: u64 = 0x2545f4914f6cdd1d;
+        let mut random = |range: i64| {
+            seed ^= seed << 13;
+            seed ^= seed >> 7;
+            seed ^= seed << 17;
+            (seed % range as u64) as i64
+        };
+        let robots = (0..8).flat_map(|x| (0..5).map(move |y| (x + 10, y + 20)))
+            .map(|(x, y)| {
+                let velocity = (random(width - 1) + 1, random(height - 1) + 1);
+                Robot { position: (x - velocity.0 * tick, y - velocity.1 * tick), velocity }
+            })
+            .collect();
+        RobotArena::new(width, height, robots)
+    }
+
+    #[test]
+    fn metrics_agree_on_tree() {
+        let arena = block_at(500, 31, 37);
+        for metric in METRICS {
+            let brute_force = least_disordered((0..arena.period()).map(|ticks| Some(arena.after(ticks).disorder(metric))));
+            assert_eq!(brute_force, Some(500), "{}", metric.name());
+            assert_eq!(arena.find_tree(metric), 500, "{}", metric.name());
+        }
+    }
+}
@@ -1,80 +1,57 @@
-use std::collections::{HashMap, HashSet};
-use regex::Regex;
-use aocutil::coord::Coord;
-use aocutil::grid::Grid;
+use std::env;
+use std::fs;
+use std::time::Instant;
+
+use arena::RobotArena;
+use metric::{parse_metric, Variance, METRICS};
+
+mod arena;
+mod metric;
 
 const DAY: u8 = 14;
 const WIDTH: i64 = 101;
 const HEIGHT: i64 = 103;
 
+/// Usage: `day14 [METRIC | compare] [FILE [WIDTH HEIGHT]]`
+///
+/// Finds the tree with the given metric, `variance`, `entropy` or `adjacency`, defaulting to
+/// variance. With `compare`, reports the tick and time taken by every metric instead. A file of
+/// robots is assumed to be for a 101x103 arena unless another size is given, e.g. `11 7` for the
+/// example.
 fn main() {
-    let input = aocutil::load_input(DAY);
-    let robots = parse_input(&input);
-
-    let part1 = quadrant_score_after(&robots, 100);
-    println!("Part 1: {part1}");
-
-    let max_interesting_ticks = (0..WIDTH * HEIGHT)
-        .max_by_key(|&i| interesting_score_after(&robots, i))
-        .unwrap();
-    println!("Part 2: {max_interesting_ticks}");
-    print(&robot_positions_after(&robots, max_interesting_ticks));
-}
-
-fn interesting_score_after(start: &[(Coord, Coord)], ticks: i64) -> usize {
-    let robots = robot_positions_after(start, ticks);
-    let coords: HashSet<_> = robots.iter().collect();
-    robots.iter()
-        .filter(|&(x, y)| coords.contains(&(x+1, *y)) )
-        .count()
-}
-
-fn quadrant_score_after(start: &[(Coord, Coord)], ticks: i64) -> i64 {
-    let coords = robot_positions_after(start, ticks);
-    quadrant_score(&coords)
-}
-
-fn robot_positions_after(start: &[(Coord, Coord)], ticks: i64) -> Vec<Coord> {
-    start.iter()
-        .map(|&(p, v)| robot_position_after(p, v, ticks))
-        .collect()
-}
-
-fn robot_position_after(p: Coord, v: Coord, ticks: i64) -> Coord {
-    ((p.0 + v.0 * ticks).rem_euclid(WIDTH), (p.1 + v.1 * ticks).rem_euclid(HEIGHT))
-}
-
-fn quadrant_score(robots: &[Coord]) -> i64 {
-    let qs: HashMap<(bool, bool), i64> = robots.iter()
-        .filter_map(|&c| quadrant(c))
-        .fold(HashMap::new(), |mut acc, c| {
-            *acc.entry(c).or_insert(0) += 1;
-            acc
-        });
-
-    qs.values().product::<i64>()
-}
-
-fn quadrant(c: Coord) -> Option<(bool, bool)> {
-    if c.0 == WIDTH / 2 || c.1 == HEIGHT / 2 {
-        None
-    } else {
-        Some(( c.0 > WIDTH / 2, c.1 > HEIGHT / 2))
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    let command = args.first()
+        .filter(|arg| *arg == "compare" || parse_metric(arg).is_some())
+        .cloned();
+    if command.is_some() {
+        args.remove(0);
+    }
+    let arena = match &args[..] {
+        [] => RobotArena::parse(&aocutil::load_input(DAY), WIDTH, HEIGHT),
+        [filename, size @ ..] => {
+            let input = fs::read_to_string(filename).unwrap_or_else(|e| panic!("Unable to read file {}: {}", filename, e));
+            let (width, height) = match size {
+                [] => (WIDTH, HEIGHT),
+                [width, height] => (width.parse().expect("Invalid width"), height.parse().expect("Invalid height")),
+                _ => panic!("Usage: day14 [METRIC | compare] [FILE [WIDTH HEIGHT]]"),
+            };
+            RobotArena::parse(&input, width, height)
+        }
+    };
+
+    if command.as_deref() == Some("compare") {
+        for metric in METRICS {
+            let start = Instant::now();
+            let ticks = arena.find_tree(metric);
+            println!("{}: {ticks} ticks, found in {:?}", metric.name(), start.elapsed());
+        }
+        return;
     }
-}
 
-fn print(robots: &[Coord]) {
-    let grid = Grid::new_with_coords(robots.iter(), '*');
-    grid.print();
-}
+    println!("Part 1: {}", arena.after(100).safety_factor());
 
-fn parse_input(input: &str) -> Vec<(Coord, Coord)> {
-    let re = Regex::new(r"^p=(.+),(.+) +v=(.+),(.+)$").unwrap();
-    input.lines()
-        .map(|line| {
-            let (_, values) = re.captures(line).unwrap().extract::<4>();
-            let n: Vec<i64> = values.iter().map(|s| s.parse().unwrap()).collect();
-            ((n[0], n[1]), (n[2], n[3]))
-        })
-        .collect()
+    let metric = command.as_deref().and_then(parse_metric).unwrap_or(&Variance);
+    let tree_ticks = arena.find_tree(metric);
+    println!("Part 2: {tree_ticks}");
+    arena.after(tree_ticks).to_grid().print();
 }
@@ -0,0 +1,81 @@
+use std::collections::{HashMap, HashSet};
+
+use aocutil::coord::Coord;
+
+/// A measure of how disordered the robots are. The picture of the tree is the most ordered
+/// arrangement, so detectors look for the tick with the lowest score.
+pub trait Metric {
+    fn name(&self) -> &str;
+
+    /// Scores one axis of the robots' positions on its own, or `None` if the metric only applies to
+    /// whole arrangements. Metrics that can score each axis let the detector search the axes
+    /// separately, which takes `WIDTH + HEIGHT` ticks rather than `WIDTH * HEIGHT`.
+    fn axis_disorder(&self, _values: &[i64]) -> Option<f64> {
+        None
+    }
+
+    fn disorder(&self, robots: &[Coord]) -> f64;
+}
+
+/// How spread out the robots are from their mean position.
+pub struct Variance;
+
+/// The Shannon entropy of the robots' rows and columns, which is low when many share a row or column.
+pub struct Entropy;
+
+/// The number of robots without a neighbour directly to their right, which is low when they form
+/// horizontal lines.
+pub struct Adjacency;
+
+impl Metric for Variance {
+    fn name(&self) -> &str {
+        "variance"
+    }
+    fn axis_disorder(&self, values: &[i64]) -> Option<f64> {
+        let n = values.len() as f64;
+        let mean = values.iter().sum::<i64>() as f64 / n;
+        Some(values.iter().map(|&v| (v as f64 - mean).powi(2)).sum::<f64>() / n)
+    }
+    fn disorder(&self, robots: &[Coord]) -> f64 {
+        let (xs, ys): (Vec<i64>, Vec<i64>) = robots.iter().copied().unzip();
+        self.axis_disorder(&xs).unwrap() + self.axis_disorder(&ys).unwrap()
+    }
+}
+
+impl Metric for Entropy {
+    fn name(&self) -> &str {
+        "entropy"
+    }
+    fn axis_disorder(&self, values: &[i64]) -> Option<f64> {
+        Some(entropy(values))
+    }
+    fn disorder(&self, robots: &[Coord]) -> f64 {
+        let (xs, ys): (Vec<i64>, Vec<i64>) = robots.iter().copied().unzip();
+        entropy(&xs) + entropy(&ys)
+    }
+}
+
+impl Metric for Adjacency {
+    fn name(&self) -> &str {
+        "adjacency"
+    }
+    fn disorder(&self, robots: &[Coord]) -> f64 {
+        let coords: HashSet<&Coord> = robots.iter().collect();
+        robots.iter().filter(|&&(x, y)| !coords.contains(&(x + 1, y))).count() as f64
+    }
+}
+
+fn entropy<T: Eq + std::hash::Hash>(values: &[T]) -> f64 {
+    let mut counts: HashMap<&T, usize> = HashMap::new();
+    for value in values {
+        *counts.entry(value).or_insert(0) += 1;
+    }
+    let n = values.len() as f64;
+    counts.values().map(|&count| count as f64 / n).map(|p| -p * p.log2()).sum()
+}
+
+pub const METRICS: [&dyn Metric; 3] = [&Variance, &Entropy, &Adjacency];
+
+pub fn parse_metric(name: &str) -> Option<&'static dyn Metric> {
+    METRICS.into_iter().find(|metric| metric.name() == name)
+}
@@ -0,0 +1,463 @@
+use std::collections::{HashMap, HashSet};
+
+use aocutil::coord::Coord;
+use aocutil::direction::{Direction, COMPASS};
+use aocutil::grid::Grid;
+
+/// What happened when the robot tried to move.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Move {
+    pub direction: char,
+    /// The robot's position before the move
+    pub from: Coord,
+    /// False if the robot, or a box it pushed, was blocked by a wall
+    pub moved: bool,
+    /// The left-hand cell of each box pushed, before the move
+    pub boxes: Vec<Coord>,
+}
+
+/// The first move after which the warehouse was found to be inconsistent.
+#[derive(Debug, PartialEq)]
+pub struct Violation {
+    /// The number of moves made, or 0 if the warehouse was inconsistent to begin with
+    pub moves: usize,
+    pub problem: String,
+}
+
+/// A warehouse of walls and boxes, with a robot pushing the boxes around. Boxes may be any
+/// number of cells wide, and are drawn as `O` if they are one cell wide, or otherwise as `[]`
+/// with `=` filling any cells in between.
+#[derive(Clone)]
+pub struct Warehouse {
+    walls: HashSet<Coord>,
+    size: Coord,
+    robot: Coord,
+    /// The left-hand cell and width of each box
+    boxes: Vec<(Coord, i64)>,
+    /// The box occupying each cell
+    cells: HashMap<Coord, usize>,
+    history: Vec<Move>,
+}
+
+impl Warehouse {
+    pub fn parse(map: &str) -> Warehouse {
+        let grid = Grid::parse(map);
+        let robot = grid.find_cell('@').expect("Warehouse must have a robot");
+        let mut boxes = grid.find_cells('O').into_iter().map(|left| (left, 1)).collect::<Vec<_>>();
+        for left in grid.find_cells('[') {
+            let width = (1..)
+                .find(|&i| grid.get((left.0 + i, left.1)) != Some('='))
+                .filter(|&i| grid.get((left.0 + i, left.1)) == Some(']'))
+                .unwrap_or_else(|| panic!("Unterminated box at {left:?}"));
+            boxes.push((left, width + 1));
+        }
+        let walls = grid.find_cells('#').into_iter().collect();
+        Warehouse::new(walls, grid.get_size(), robot, boxes)
+    }
+
+    fn new(walls: HashSet<Coord>, size: Coord, robot: Coord, boxes: Vec<(Coord, i64)>) -> Warehouse {
+        let mut warehouse = Warehouse { walls, size, robot, boxes, cells: HashMap::new(), history: Vec::new() };
+        for index in 0..warehouse.boxes.len() {
+            warehouse.place(index);
+        }
+        warehouse
+    }
+
+    /// A copy of the warehouse stretched horizontally, so that every cell becomes `factor` cells
+    /// wide, including the boxes.
+    pub fn scaled(&self, factor: i64) -> Warehouse {
+        assert!(factor > 0, "Scale must be positive");
+        let stretch = |(x, y): Coord| (x * factor, y);
+        let walls = self.walls.iter().flat_map(|&(x, y)| (0..factor).map(move |i| (x * factor + i, y))).collect();
+        let boxes = self.boxes.iter().map(|&(left, width)| (stretch(left), width * factor)).collect();
+        Warehouse::new(walls, (self.size.0 * factor, self.size.1), stretch(self.robot), boxes)
+    }
+
+    /// The moves made so far, oldest first.
+    pub fn history(&self) -> &[Move] {
+        &self.history
+    }
+
+    /// Tries to move the robot one step, pushing any boxes in the way. If anything would be pushed
+    /// into a wall, nothing moves.
+    pub fn step(&mut self, direction: char) -> &Move {
+        let d = parse_direction(direction);
+        let mut pushed: Vec<usize> = Vec::new();
+        let mut targets = vec![d.step(self.robot)];
+        let mut moved = true;
+        while let Some(target) = targets.pop() {
+            if self.walls.contains(&target) {
+                moved = false;
+                break;
+            }
+            if let Some(&index) = self.cells.get(&target) {
+                if !pushed.contains(&index) {
+                    pushed.push(index);
+                    // The cells the box moves into, other than those it already occupies
+                    targets.extend(self.box_cells(index).map(|cell| d.step(cell)).filter(|cell| self.cells.get(cell) != Some(&index)));
+                }
+            }
+        }
+
+        let from = self.robot;
+        let boxes = if moved { pushed.iter().map(|&index| self.boxes[index].0).collect() } else { Vec::new() };
+        if moved {
+            self.shift(&pushed, d.delta());
+            self.robot = d.step(self.robot);
+        }
+        self.history.push(Move { direction, from, moved, boxes });
+        self.history.last().unwrap()
+    }
+
+    /// Reverts the last move, returning it, or `None` if no moves have been made.
+    pub fn undo(&mut self) -> Option<Move> {
+        let last = self.history.pop()?;
+        if last.moved {
+            let delta = parse_direction(last.direction).delta();
+            let pushed: Vec<usize> = last.boxes.iter()
+                .map(|&(x, y)| self.cells[&(x + delta.0, y + delta.1)])
+                .collect();
+            self.shift(&pushed, (-delta.0, -delta.1));
+            self.robot = last.from;
+        }
+        Some(last)
+    }
+
+    /// Makes the moves in order, ignoring line breaks, stopping after `stop_after` moves if given.
+    pub fn replay(&mut self, moves: &str, stop_after: Option<usize>) {
+        for direction in moves.chars().filter(|c| !c.is_whitespace()).take(stop_after.unwrap_or(usize::MAX)) {
+            self.step(direction);
+        }
+    }
+
+    /// Like [`Warehouse::replay`], but checks the invariants before the first move and after every
+    /// move, stopping at the first that breaks them.
+    pub fn replay_validated(&mut self, moves: &str) -> Result<(), Violation> {
+        let check = |warehouse: &Warehouse| warehouse.check_invariants()
+            .map_err(|problem| Violation { moves: warehouse.history.len(), problem });
+        check(self)?;
+        for direction in moves.chars().filter(|c| !c.is_whitespace()) {
+            self.step(direction);
+            check(self)?;
+        }
+        Ok(())
+    }
+
+    /// Checks that every box occupies exactly its own cells, in one piece and within the
+    /// warehouse, and that nothing overlaps a wall, the robot or another box.
+    pub fn check_invariants(&self) -> Result<(), String> {
+        let in_bounds = |(x, y): Coord| (0..self.size.0).contains(&x) && (0..self.size.1).contains(&y);
+        if self.walls.contains(&self.robot) || !in_bounds(self.robot) {
+            return Err(format!("Robot is at {:?}, in a wall or outside the warehouse", self.robot));
+        }
+        for (index, &(left, width)) in self.boxes.iter().enumerate() {
+            if width < 1 {
+                return Err(format!("Box at {left:?} has width {width}"));
+            }
+            for cell in self.box_cells(index) {
+                if self.cells.get(&cell) != Some(&index) {
+                    return Err(format!("Box at {left:?} is missing its cell {cell:?}"));
+                }
+                if self.walls.contains(&cell) || !in_bounds(cell) {
+                    return Err(format!("Box at {left:?} is in a wall or outside the warehouse at {cell:?}"));
+                }
+                if cell == self.robot {
+                    return Err(format!("Box at {left:?} overlaps the robot"));
+                }
+            }
+        }
+        // Every box has all its cells, so any extra cell must be left over from a box that moved
+        let occupied: i64 = self.boxes.iter().map(|&(_, width)| width).sum();
+        if self.cells.len() as i64 != occupied {
+            return Err(format!("{} cells are marked as boxes, but the boxes cover {occupied}", self.cells.len()));
+        }
+        Ok(())
+    }
+
+    /// The sum of the GPS coordinates of the boxes, measured from their left-hand edges.
+    pub fn gps_score(&self) -> i64 {
+        self.boxes.iter().map(|&((x, y), _)| x + 100 * y).sum()
+    }
+
+    pub fn to_grid(&self) -> Grid {
+        let mut grid = Grid::new();
+        for y in 0..self.size.1 {
+            for x in 0..self.size.0 {
+                grid.set((x, y), if self.walls.contains(&(x, y)) { '#' } else { '.' });
+            }
+        }
+        for &((x, y), width) in &self.boxes {
+            for i in 0..width {
+                let cell = match (i, width) {
+                    (_, 1) => 'O',
+                    (0, _) => '[',
+                    _ if i == width - 1 => ']',
+                    _ => '=',
+                };
+                grid.set((x + i, y), cell);
+            }
+        }
+        grid.set(self.robot, '@');
+        grid
+    }
+
+    fn box_cells(&self, index: usize) -> impl Iterator<Item = Coord> {
+        let ((x, y), width) = self.boxes[index];
+        (0..width).map(move |i| (x + i, y))
+    }
+
+    fn place(&mut self, index: usize) {
+        for cell in self.box_cells(index).collect::<Vec<_>>() {
+            if self.cells.insert(cell, index).is_some() {
+                panic!("Boxes overlap at {cell:?}");
+            }
+        }
+    }
+
+    fn shift(&mut self, indices: &[usize], (dx, dy): Coord) {
+        for &index in indices {
+            for cell in self.box_cells(index).collect::<Vec<_>>() {
+                self.cells.remove(&cell);
+            }
+        }
+        for &index in indices {
+            let ((x, y), width) = self.boxes[index];
+            self.boxes[index] = ((x + dx, y + dy), width);
+            self.place(index);
+        }
+    }
+}
+
+pub fn parse_direction(c: char) -> &'static Direction {
+    match c {
+        '^' => COMPASS.north(),
+        '>' => COMPASS.east(),
+        'v' => COMPASS.south(),
+        '<' => COMPASS.west(),
+        _ => panic!("Not a valid direction: '{c}'")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::parse_input;
+
+    /// A xorshift generator, so that random warehouses are the same on every run.
+    struct Rng(u64);
+
+    impl Rng {
+        fn below(&mut self, n: u64) -> u64 {
+            self.0 ^= self.0 << 13;
+            self.0 ^= self.0 >> 7;
+            self.0 ^= self.0 << 17;
+            self.0 % n
+        }
+    }
+
+    fn random_warehouse(rng: &mut Rng) -> String {
+        let (width, height) = (5 + rng.below(8) as i64, 5 + rng.below(6) as i64);
+        let robot = (1 + rng.below(width as u64 - 2) as i64, 1 + rng.below(height as u64 - 2) as i64);
+        let mut map = String::new();
+        for y in 0..height {
+            for x in 0..width {
+                map.push(match rng.below(20) {
+                    _ if (x, y) == robot => '@',
+                    _ if x == 0 || y == 0 || x == width - 1 || y == height - 1 => '#',
+                    0..=2 => '#',
+                    3..=8 => 'O',
+                    _ => '.',
+                });
+            }
+            map.push('\n');
+        }
+        map
+    }
+
+    fn random_moves(rng: &mut Rng, directions: &[char]) -> String {
+        (0..rng.below(80)).map(|_| directions[rng.below(directions.len() as u64) as usize]).collect()
+    }
+
+    /// The original simulation of narrow boxes, returning the robot and GPS score after each move.
+    fn reference_narrow(grid: &Grid, moves: &str) -> Vec<(Coord, i64)> {
+        let mut robot = grid.find_cell('@').unwrap();
+        let mut boxes: HashSet<Coord> = grid.find_cells('O').into_iter().collect();
+        let mut states = Vec::new();
+        for d in moves.chars().map(parse_direction) {
+            let robot_target = d.step(robot);
+            let mut box_stack: Vec<Coord> = Vec::new();
+            let mut pusher_target = robot_target;
+            while boxes.contains(&pusher_target) {
+                box_stack.push(pusher_target);
+                pusher_target = d.step(pusher_target);
+            }
+            if grid.get(pusher_target).unwrap() != '#' {
+                if !box_stack.is_empty() {
+                    boxes.remove(&robot_target);
+                    boxes.insert(pusher_target);
+                }
+                robot = robot_target;
+            }
+            states.push((robot, boxes.iter().map(|&(x, y)| x + 100 * y).sum()));
+        }
+        states
+    }
+
+    /// The original simulation of wide boxes, returning the robot and GPS score after each move.
+    fn reference_wide(grid: &Grid, moves: &str) -> Vec<(Coord, i64)> {
+        let mut robot = grid.find_cell('@').unwrap();
+        let mut box_lefts: HashSet<Coord> = grid.find_cells('[').into_iter().collect();
+        let mut box_rights: HashSet<Coord> = grid.find_cells(']').into_iter().collect();
+        let mut states = Vec::new();
+        for d in moves.chars().map(parse_direction) {
+            let robot_target = d.step(robot);
+            let mut to_move = Vec::new();
+            let mut pusher_targets = vec![robot_target];
+            loop {
+                if pusher_targets.iter().any(|&t| grid.get_or(t, '.') == '#') {
+                    break;
+                }
+                let pushed: Vec<(Coord, Coord)> = pusher_targets.iter()
+                    .filter_map(|&t| if box_lefts.contains(&t) {
+                        Some((t, COMPASS.east().step(t)))
+                    } else if box_rights.contains(&t) {
+                        Some((COMPASS.west().step(t), t))
+                    } else {
+                        None
+                    })
+                    .collect();
+                if pushed.is_empty() {
+                    break;
+                }
+                pusher_targets = Vec::new();
+                for (left, right) in pushed {
+                    to_move.push((left, right));
+                    if d != COMPASS.east() {
+                        pusher_targets.push(d.step(left));
+                    }
+                    if d != COMPASS.west() {
+                        pusher_targets.push(d.step(right));
+                    }
+                }
+            }
+            if !pusher_targets.iter().any(|&t| grid.get_or(t, '.') == '#') {
+                to_move.iter().for_each(|(left, right)| { box_lefts.remove(left); box_rights.remove(right); });
+                to_move.iter().for_each(|&(left, right)| { box_lefts.insert(d.step(left)); box_rights.insert(d.step(right)); });
+                robot = robot_target;
+            }
+            states.push((robot, box_lefts.iter().map(|&(x, y)| x + 100 * y).sum()));
+        }
+        states
+    }
+
+    fn replay_states(warehouse: &mut Warehouse, moves: &str) -> Vec<(Coord, i64)> {
+        moves.chars()
+            .map(|direction| {
+                warehouse.step(direction);
+                (warehouse.robot, warehouse.gps_score())
+            })
+            .collect()
+    }
+
+    #[test]
+    fn scores_examples() {
+        let (map, moves) = parse_input(&fs::read_to_string("test_input.txt").unwrap());
+        let mut warehouse = Warehouse::parse(&map);
+        let mut wide = warehouse.scaled(2);
+        warehouse.replay(&moves, None);
+        assert_eq!(warehouse.gps_score(), 10092);
+        wide.replay(&moves, None);
+        assert_eq!(wide.gps_score(), 9021);
+    }
+
+    #[test]
+    fn steps_and_undoes_moves() {
+        let mut warehouse = Warehouse::parse("#######\n#...#.#\n#.....#\n#..OO@#\n#..O..#\n#.....#\n#######").scaled(2);
+        let start = format!("{:?}", warehouse.to_grid());
+        assert_eq!(start.lines().nth(3), Some("##....[][]@.##"));
+
+        let pushed = warehouse.step('<').clone();
+        assert_eq!(pushed, Move { direction: '<', from: (10, 3), moved: true, boxes: vec![(8, 3), (6, 3)] });
+        warehouse.replay("vv<<^^<<^^", None);
+        assert_eq!(format!("{:?}", warehouse.to_grid()), "##############
+##...[].##..##
+##...@.[]...##
+##....[]....##
+##..........##
+##..........##
+##############
+");
+        assert_eq!(warehouse.history().iter().filter(|m| !m.moved).count(), 1);
+
+        while warehouse.undo().is_some() {}
+        assert_eq!(format!("{:?}", warehouse.to_grid()), start);
+        assert!(warehouse.history().is_empty());
+    }
+
+    #[test]
+    fn pushes_boxes_of_any_width() {
+        let mut warehouse = Warehouse::parse("#########\n#.......#\n#..[=]..#\n#...[]..#\n#....@..#\n#########");
+        warehouse.replay("^<^>", Some(3));
+        assert_eq!(warehouse.history().len(), 3);
+        assert_eq!(warehouse.history()[0].boxes, vec![(4, 3), (3, 2)]);
+        assert!(!warehouse.history()[2].moved);
+        let grid = format!("{:?}", warehouse.to_grid());
+        assert_eq!(grid.lines().collect::<Vec<_>>()[1..4], ["#..[=]..#", "#...[]..#", "#...@...#"]);
+        let grid = warehouse.scaled(3).to_grid();
+        assert_eq!((grid.get((9, 1)), grid.get((10, 1)), grid.get((17, 1))), (Some('['), Some('='), Some(']')));
+    }
+
+    #[test]
+    fn reports_first_violation() {
+        let mut warehouse = Warehouse::parse("######\n#.O@.#\n######");
+        assert_eq!(warehouse.replay_validated("<<>"), Ok(()));
+        // Corrupt the warehouse by moving a box without updating its cells
+        warehouse.boxes[0].0 = (3, 1);
+        let violation = warehouse.replay_validated(">").unwrap_err();
+        assert_eq!(violation.moves, 3);
+        assert!(violation.problem.contains("missing"), "{}", violation.problem);
+    }
+
+    #[test]
+    fn random_warehouses_match_reference_and_keep_invariants() {
+        let mut rng = Rng(0x2024_1215);
+        for _ in 0..300 {
+            let map = random_warehouse(&mut rng);
+            let moves = random_moves(&mut rng, &['^', 'v', '<', '>']);
+            let narrow = Warehouse::parse(&map);
+            for scale in 1..=3 {
+                let mut warehouse = narrow.scaled(scale);
+                let start = format!("{:?}", warehouse.to_grid());
+                assert_eq!(warehouse.clone().replay_validated(&moves), Ok(()), "{start}{moves}");
+                let states = replay_states(&mut warehouse, &moves);
+                match scale {
+                    1 => assert_eq!(states, reference_narrow(&Grid::parse(&start), &moves), "{start}{moves}"),
+                    2 => assert_eq!(states, reference_wide(&Grid::parse(&start), &moves), "{start}{moves}"),
+                    _ => {}
+                }
+                while warehouse.undo().is_some() {}
+                assert_eq!(format!("{:?}", warehouse.to_grid()), start);
+            }
+        }
+    }
+
+    #[test]
+    fn vertical_moves_commute_with_scaling() {
+        // Without horizontal moves, every box stays aligned with the cells of the narrow warehouse
+        let mut rng = Rng(0x1224_2015);
+        for _ in 0..300 {
+            let map = random_warehouse(&mut rng);
+            let moves = random_moves(&mut rng, &['^', 'v']);
+            let narrow = Warehouse::parse(&map);
+            for scale in 2..=3 {
+                let mut wide = narrow.scaled(scale);
+                wide.replay(&moves, None);
+                let mut expected = narrow.clone();
+                expected.replay(&moves, None);
+                assert_eq!(format!("{:?}", wide.to_grid()), format!("{:?}", expected.scaled(scale).to_grid()), "{map}{moves}");
+            }
+        }
+    }
+}
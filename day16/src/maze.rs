@@ -0,0 +1,211 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+use aocutil::coord::Coord;
+use aocutil::direction::{Direction, Directions, COMPASS};
+use aocutil::grid::Grid;
+
+/// A tile and the index of the direction faced, in the order north, east, south, west, so that
+/// turning right adds one.
+type State = (Coord, usize);
+
+/// The costs of moving through the maze.
+pub struct Rules {
+    pub step_cost: i64,
+    /// The cost of turning 90 degrees on the spot
+    pub turn_cost: i64,
+    /// The cost of turning around on the spot, or `None` if that takes two 90 degree turns
+    pub reverse_cost: Option<i64>,
+    pub start_directions: Vec<&'static Direction>,
+}
+
+impl Rules {
+    /// The reindeer's rules: a point to step forwards, a thousand to turn, and starting east.
+    pub fn reindeer() -> Rules {
+        Rules { step_cost: 1, turn_cost: 1000, reverse_cost: None, start_directions: vec![COMPASS.east()] }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Solution {
+    pub score: i64,
+    /// The tiles along one of the best paths, from a start to a goal
+    pub path: Vec<Coord>,
+    /// Every tile on any of the best paths
+    pub tiles: HashSet<Coord>,
+}
+
+pub struct Maze {
+    open: HashSet<Coord>,
+    starts: Vec<Coord>,
+    goals: HashSet<Coord>,
+}
+
+impl Maze {
+    /// Parses a maze of `#` walls, with any number of tiles marked `start` and `goal`.
+    pub fn parse(input: &str, start: char, goal: char) -> Maze {
+        let grid = Grid::parse(input);
+        let open = grid.all_coords().into_iter().filter(|&c| grid.get(c) != Some('#')).collect();
+        let starts = grid.find_cells(start);
+        let goals: HashSet<Coord> = grid.find_cells(goal).into_iter().collect();
+        assert!(!starts.is_empty() && !goals.is_empty(), "Maze must have a start '{start}' and a goal '{goal}'");
+        Maze { open, starts, goals }
+    }
+
+    /// Finds the lowest score from any start to any goal, or `None` if no goal can be reached.
+    ///
+    /// Runs Dijkstra's algorithm over (tile, direction) states, remembering every predecessor that
+    /// reaches a state with its best score, so that all of the best paths can be traced back. Panics
+    /// if any cost is negative, as Dijkstra's algorithm needs costs that never decrease the score.
+    pub fn solve(&self, rules: &Rules) -> Option<Solution> {
+        let costs = [rules.step_cost, rules.turn_cost, rules.reverse_cost.unwrap_or(0)];
+        assert!(costs.iter().all(|&cost| cost >= 0), "Costs must not be negative");
+        let directions = COMPASS.directions();
+        let mut best: HashMap<State, i64> = HashMap::new();
+        let mut previous: HashMap<State, Vec<State>> = HashMap::new();
+        let mut queue = BinaryHeap::new();
+        for &start in &self.starts {
+            for &direction in &rules.start_directions {
+                let index = directions.iter().position(|&d| d == direction).expect("Start directions must be on the compass");
+                best.insert((start, index), 0);
+                queue.push(Reverse((0, start, index)));
+            }
+        }
+
+        let mut finish: Option<(i64, Vec<State>)> = None;
+        while let Some(Reverse((score, coord, facing))) = queue.pop() {
+            if best[&(coord, facing)] < score || finish.as_ref().is_some_and(|&(best_score, _)| score > best_score) {
+                continue;
+            }
+            if self.goals.contains(&coord) {
+                finish.get_or_insert_with(|| (score, Vec::new())).1.push((coord, facing));
+                continue;
+            }
+            let forward = directions[facing].step(coord);
+            let mut moves = vec![
+                ((coord, (facing + 3) % 4), rules.turn_cost),
+                ((coord, (facing + 1) % 4), rules.turn_cost),
+            ];
+            if self.open.contains(&forward) {
+                moves.push(((forward, facing), rules.step_cost));
+            }
+            if let Some(cost) = rules.reverse_cost {
+                moves.push(((coord, (facing + 2) % 4), cost));
+            }
+            for (next, cost) in moves {
+                let next_score = score + cost;
+                match best.get(&next) {
+                    Some(&best_score) if best_score < next_score => continue,
+                    Some(&best_score) if best_score == next_score => {}
+                    _ => {
+                        best.insert(next, next_score);
+                        previous.insert(next, Vec::new());
+                        queue.push(Reverse((next_score, next.0, next.1)));
+                    }
+                }
+                // Starts have no predecessors, even when turning on the spot for free leads back to them
+                if let Some(states) = previous.get_mut(&next) {
+                    states.push((coord, facing));
+                }
+            }
+        }
+
+        let (score, ends) = finish?;
+        let mut tiles = HashSet::new();
+        let mut seen: HashSet<State> = ends.iter().copied().collect();
+        let mut stack = ends.clone();
+        while let Some(state) = stack.pop() {
+            tiles.insert(state.0);
+            for &before in previous.get(&state).into_iter().flatten() {
+                if seen.insert(before) {
+                    stack.push(before);
+                }
+            }
+        }
+
+        let mut path = vec![ends[0].0];
+        let mut state = ends[0];
+        while let Some(&before) = previous.get(&state).and_then(|states| states.first()) {
+            if before.0 != state.0 {
+                path.push(before.0);
+            }
+            state = before;
+        }
+        path.reverse();
+        Some(Solution { score, path, tiles })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    const SECOND_EXAMPLE: &str = "#################
+#...#...#...#..E#
+#.#.#.#.#.#.#.#.#
+#.#.#.#...#...#.#
+#.#.#.#.###.#.#.#
+#...#.#.#.....#.#
+#.#.#.#.#.#####.#
+#.#...#.#.#.....#
+#.#.#####.#.###.#
+#.#.#.......#...#
+#.#.###.#####.###
+#.#.#...#.....#.#
+#.#.#.#####.###.#
+#.#.#.........#.#
+#.#.#.#########.#
+#S#.............#
+#################";
+
+    #[test]
+    fn solves_examples() {
+        let maze = Maze::parse(&fs::read_to_string("test_input.txt").unwrap(), 'S', 'E');
+        let solution = maze.solve(&Rules::reindeer()).unwrap();
+        assert_eq!((solution.score, solution.tiles.len()), (7036, 45));
+        assert_eq!(solution.path.first(), Some(&(1, 13)));
+        assert_eq!(solution.path.last(), Some(&(13, 1)));
+        assert!(solution.path.iter().all(|tile| solution.tiles.contains(tile)));
+
+        let solution = Maze::parse(SECOND_EXAMPLE, 'S', 'E').solve(&Rules::reindeer()).unwrap();
+        assert_eq!((solution.score, solution.tiles.len()), (11048, 64));
+    }
+
+    #[test]
+    fn supports_other_rules() {
+        let maze = Maze::parse(&fs::read_to_string("test_input.txt").unwrap(), 'S', 'E');
+        // Without turning costs, the score is the length of the shortest path
+        let free_turns = Rules { step_cost: 1, turn_cost: 0, reverse_cost: None, start_directions: vec![COMPASS.east()] };
+        let solution = maze.solve(&free_turns).unwrap();
+        assert_eq!(solution.score, solution.path.len() as i64 - 1);
+
+        // Starting north saves the first turn
+        let any_direction = Rules { start_directions: COMPASS.directions(), ..Rules::reindeer() };
+        assert_eq!(maze.solve(&any_direction).unwrap().score, 6036);
+
+        // A dead end that must be left the way it was entered
+        let corridor = Maze::parse("#####\n#E.S#\n#####", 'S', 'E');
+        assert_eq!(corridor.solve(&Rules::reindeer()).unwrap().score, 2002);
+        let cheap_reverse = Rules { reverse_cost: Some(500), ..Rules::reindeer() };
+        assert_eq!(corridor.solve(&cheap_reverse).unwrap().score, 502);
+    }
+
+    #[test]
+    #[should_panic(expected = "Costs must not be negative")]
+    fn rejects_negative_costs() {
+        let corridor = Maze::parse("#####\n#E.S#\n#####", 'S', 'E');
+        corridor.solve(&Rules { turn_cost: -1, ..Rules::reindeer() });
+    }
+
+    #[test]
+    fn handles_several_goals_and_unreachable_ones() {
+        let maze = Maze::parse("#######\n#E.S.E#\n#######", 'S', 'E');
+        let solution = maze.solve(&Rules::reindeer()).unwrap();
+        assert_eq!(solution.score, 2);
+        assert_eq!(solution.path, vec![(3, 1), (4, 1), (5, 1)]);
+        let walled = Maze::parse("#####\n#S#E#\n#####", 'S', 'E');
+        assert_eq!(walled.solve(&Rules::reindeer()), None);
+    }
+}
@@ -0,0 +1,207 @@
+use std::collections::HashMap;
+
+use crate::{ADV, BDV, BST, BXC, BXL, CDV, JNZ, OUT};
+
+const MNEMONICS: [(&str, i64); 8] = [
+    ("adv", ADV), ("bxl", BXL), ("bst", BST), ("jnz", JNZ),
+    ("bxc", BXC), ("out", OUT), ("bdv", BDV), ("cdv", CDV),
+];
+
+/// Assembles mnemonic source into the puzzle input format (`Register A/B/C` and `Program:` lines).
+///
+/// Each line holds an optional `label:`, an optional instruction and an optional `;` comment.
+/// Register initial values are set with `.a`, `.b` and `.c` directives and default to zero.
+/// Combo operands may be a literal `0` to `3` or a register `a`, `b` or `c`, as combo operand 7 is
+/// reserved. Jump targets may be a literal or a label. The operand of `bxc` is ignored by the
+/// computer, so may be omitted.
+///
+/// ```text
+/// .a 2024
+/// loop:   adv 1       ; a = a >> 1
+///         out a
+///         jnz loop
+/// ```
+pub fn assemble(source: &str) -> Result<String, String> {
+    let lines: Vec<(usize, &str)> = source.lines()
+        .map(|line| line.split(';').next().unwrap().trim())
+        .enumerate()
+        .map(|(n, line)| (n + 1, line))
+        .collect();
+
+    // First pass collects label addresses so that jumps can refer forwards
+    let mut labels = HashMap::new();
+    let mut address = 0;
+    for &(n, line) in &lines {
+        let (label, statement) = split_label(line);
+        if let Some(label) = label {
+            if labels.insert(label, address).is_some() {
+                return Err(format!("line {n}: duplicate label '{label}'"));
+            }
+        }
+        if !statement.is_empty() && !statement.starts_with('.') {
+            address += 2;
+        }
+    }
+
+    let mut registers = [0i64; 3];
+    let mut program = vec![];
+    for (n, line) in lines {
+        let (_, statement) = split_label(line);
+        let mut words = statement.split_whitespace();
+        let Some(mnemonic) = words.next() else { continue };
+        let operand = words.next();
+        if let Some(extra) = words.next() {
+            return Err(format!("line {n}: unexpected '{extra}'"));
+        }
+
+        if let Some(register) = mnemonic.strip_prefix('.') {
+            let index = match register.to_ascii_lowercase().as_str() {
+                "a" => 0,
+                "b" => 1,
+                "c" => 2,
+                _ => return Err(format!("line {n}: unknown directive '{mnemonic}'")),
+            };
+            let value = operand.ok_or_else(|| format!("line {n}: missing value for '{mnemonic}'"))?;
+            registers[index] = value.parse()
+                .map_err(|_| format!("line {n}: invalid register value '{value}'"))?;
+            continue;
+        }
+
+        let lower = mnemonic.to_ascii_lowercase();
+        let &(_, opcode) = MNEMONICS.iter().find(|(name, _)| *name == lower)
+            .ok_or_else(|| format!("line {n}: unknown instruction '{mnemonic}'"))?;
+        let arg = match (opcode, operand) {
+            (BXC, None) => 0,
+            (_, None) => return Err(format!("line {n}: missing operand for '{mnemonic}'")),
+            (JNZ, Some(label)) if labels.contains_key(label) => labels[label],
+            (_, Some(operand)) => parse_operand(opcode, operand)
+                .ok_or_else(|| format!("line {n}: invalid operand '{operand}' for '{mnemonic}'"))?,
+        };
+        if !(0..8).contains(&arg) {
+            return Err(format!("line {n}: operand {arg} for '{mnemonic}' does not fit in 3 bits"));
+        }
+        if is_combo(opcode) && arg == 7 {
+            return Err(format!("line {n}: combo operand 7 for '{mnemonic}' is reserved"));
+        }
+        program.push(opcode);
+        program.push(arg);
+    }
+
+    Ok(format!(
+        "Register A: {}\nRegister B: {}\nRegister C: {}\n\nProgram: {}\n",
+        registers[0], registers[1], registers[2],
+        program.iter().map(|i| i.to_string()).collect::<Vec<String>>().join(",")
+    ))
+}
+
+/// Disassembles a program into source accepted by [`assemble`], labelling jump targets. Fails on
+/// opcodes and operands that the computer cannot run.
+pub fn disassemble(registers: (i64, i64, i64), program: &[i64]) -> Result<String, String> {
+    let targets: Vec<i64> = program.chunks(2)
+        .filter(|instruction| instruction[0] == JNZ)
+        .filter_map(|instruction| instruction.get(1).copied())
+        .filter(|&target| target % 2 == 0 && (target as usize) < program.len())
+        .collect();
+
+    let mut source = format!(".a {}\n.b {}\n.c {}\n", registers.0, registers.1, registers.2);
+    for (address, instruction) in (0..).step_by(2).zip(program.chunks(2)) {
+        let label = if targets.contains(&address) { format!("L{address}:") } else { String::new() };
+        let (opcode, arg) = (instruction[0], *instruction.get(1).unwrap_or(&0));
+        let &(mnemonic, _) = MNEMONICS.iter().find(|&&(_, op)| op == opcode)
+            .ok_or_else(|| format!("address {address}: invalid opcode {opcode}"))?;
+        if !(0..8).contains(&arg) || (is_combo(opcode) && arg == 7) {
+            return Err(format!("address {address}: invalid operand {arg} for '{mnemonic}'"));
+        }
+        let operand = match opcode {
+            JNZ if targets.contains(&arg) => format!("L{arg}"),
+            _ if is_combo(opcode) => combo_name(arg),
+            _ => arg.to_string(),
+        };
+        source.push_str(&format!("{label:<8}{mnemonic} {operand}\n"));
+    }
+    Ok(source)
+}
+
+fn is_combo(opcode: i64) -> bool {
+    matches!(opcode, ADV | BST | OUT | BDV | CDV)
+}
+
+fn split_label(line: &str) -> (Option<&str>, &str) {
+    match line.split_once(':') {
+        Some((label, statement)) => (Some(label.trim()), statement.trim()),
+        None => (None, line),
+    }
+}
+
+fn parse_operand(opcode: i64, operand: &str) -> Option<i64> {
+    let combo = is_combo(opcode);
+    match operand.to_ascii_lowercase().as_str() {
+        "a" if combo => Some(4),
+        "b" if combo => Some(5),
+        "c" if combo => Some(6),
+        literal => literal.parse().ok(),
+    }
+}
+
+fn combo_name(arg: i64) -> String {
+    match arg {
+        4 => "a".to_string(),
+        5 => "b".to_string(),
+        6 => "c".to_string(),
+        _ => arg.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{parse_input, run};
+
+    fn run_source(source: &str) -> Vec<i64> {
+        let (registers, program) = parse_input(&assemble(source).unwrap());
+        run(registers, &program)
+    }
+
+    #[test]
+    fn assembles_example_program() {
+        let source = "
+            .a 729
+            start:  adv 1   ; a = a >> 1
+                    out a
+                    jnz start
+        ";
+        assert_eq!(assemble(source).unwrap(), "Register A: 729\nRegister B: 0\nRegister C: 0\n\nProgram: 0,1,5,4,3,0\n");
+        assert_eq!(run_source(source), vec![4, 6, 3, 5, 6, 3, 5, 2, 1, 0]);
+    }
+
+    #[test]
+    fn runs_readable_programs() {
+        assert_eq!(run_source(".a 10\nout 0\nout 1\nout a"), vec![0, 1, 2]);
+        assert_eq!(run_source(".c 9\nbst c\nout b"), vec![1]);
+        assert_eq!(run_source(".b 2024\n.c 43690\nbxc\nout b"), vec![2]);
+    }
+
+    #[test]
+    fn disassembly_round_trips() {
+        let input = "Register A: 41644071\nRegister B: 0\nRegister C: 0\n\nProgram: 2,4,1,2,7,5,1,7,4,4,0,3,5,5,3,0\n";
+        let (registers, program) = parse_input(input);
+        assert_eq!(assemble(&disassemble(registers, &program).unwrap()).unwrap(), input);
+    }
+
+    #[test]
+    fn reports_errors_with_line_numbers() {
+        assert_eq!(assemble("out a\nfoo 1"), Err("line 2: unknown instruction 'foo'".to_string()));
+        assert_eq!(assemble("bxl a"), Err("line 1: invalid operand 'a' for 'bxl'".to_string()));
+        assert_eq!(assemble("bxl 8"), Err("line 1: operand 8 for 'bxl' does not fit in 3 bits".to_string()));
+        assert_eq!(assemble("x: out a\nx: jnz x"), Err("line 2: duplicate label 'x'".to_string()));
+        assert_eq!(assemble("adv 1\nout 7"), Err("line 2: combo operand 7 for 'out' is reserved".to_string()));
+        assert_eq!(assemble("bxl 7").map(|_| ()), Ok(()));
+    }
+
+    #[test]
+    fn rejects_programs_that_cannot_run() {
+        assert_eq!(disassemble((0, 0, 0), &[0, 1, 8, 0]), Err("address 2: invalid opcode 8".to_string()));
+        assert_eq!(disassemble((0, 0, 0), &[2, 7]), Err("address 0: invalid operand 7 for 'bst'".to_string()));
+        assert_eq!(disassemble((0, 0, 0), &[1, -1]), Err("address 0: invalid operand -1 for 'bxl'".to_string()));
+    }
+}
@@ -1,5 +1,10 @@
+use std::{env, fs};
+
 use regex::Regex;
 
+mod asm;
+mod symbolic;
+
 const DAY: u8 = 17;
 
 const ADV: i64 = 0;
@@ -11,14 +16,58 @@ const OUT: i64 = 5;
 const BDV: i64 = 6;
 const CDV: i64 = 7;
 
+/// Usage: `day17 [disassemble|symbolic] [FILE]`
+///
+/// `FILE` defaults to this day's `input.txt`. Files ending in `.asm` are assembled before running.
 fn main() {
-    let input = aocutil::load_input(DAY);
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    let mode = match args.first().map(String::as_str) {
+        Some(mode @ ("disassemble" | "symbolic")) => mode.to_string(),
+        _ => String::new(),
+    };
+    if !mode.is_empty() {
+        args.remove(0);
+    }
+    let input = match args.first() {
+        None => aocutil::load_input(DAY),
+        Some(filename) => {
+            let content = fs::read_to_string(filename)
+                .unwrap_or_else(|e| panic!("Unable to read file {}: {}", filename, e));
+            if filename.ends_with(".asm") {
+                asm::assemble(&content).unwrap_or_else(|e| panic!("Unable to assemble {}: {}", filename, e))
+            } else {
+                content
+            }
+        }
+    };
     let (registers, program) = parse_input(&input);
 
+    match mode.as_str() {
+        "disassemble" => {
+            print!("{}", asm::disassemble(registers, &program).unwrap_or_else(|e| panic!("Unable to disassemble: {}", e)));
+            return;
+        }
+        "symbolic" => {
+            match symbolic::Iteration::analyse(&program) {
+                Some(iteration) => print!("{iteration}"),
+                None => println!("Program is not a single loop that shifts register A by a constant"),
+            }
+            match symbolic::solve_self_output(&program) {
+                Some(a) => println!("Self-output: {a}"),
+                None => println!("Self-output: no solution"),
+            }
+            return;
+        }
+        _ => {}
+    }
+
     let output = run(registers, &program);
 
     println!("Part 1: {}", output.iter().map(|i| i.to_string()).collect::<Vec<String>>().join(","));
-    println!("Part 2: {}", find_self_output(registers, &program));
+    match find_self_output(registers, &program) {
+        Some(a) => println!("Part 2: {a}"),
+        None => println!("Part 2: no value of register A reproduces the program"),
+    }
 
 }
 
@@ -56,7 +105,8 @@ fn combo(op: i64, a: i64, b: i64, c: i64) -> i64 {
     }
 }
 
-/// Decompiled `input.txt` manually. This function is not used in the solution.
+/// Decompiled `input.txt` manually. This function is not used in the solution. Run with the
+/// `symbolic` argument to derive the equivalent bit formulas automatically.
 /// The main takeaway from decompiling is that each value of output is derived from the least
 /// significant remaining 10 bits of register `a`, and the bottom 3 bits of `a` are thrown away on
 /// each iteration. 3 bit chunks mean they can be considered octal digits, with 4 octal digits
@@ -79,53 +129,52 @@ fn run_hardcoded(registers: (i64, i64, i64)) -> Vec<i64> {
     output
 }
 
-fn find_self_output(registers: (i64, i64, i64), program: &Vec<i64>) -> i64 {
-    let program_rev: Vec<i64> = program.iter().rev().copied().collect();
-
-    let mut octal_a: [i64; 16] = [1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0];
-    let mut correct_octal_digits = 0;
-    let mut counter = 0;
-    loop {
-
-        // As discovered from reverse engineering input.txt:
-        //
-        // Each value of output is derived from up to 10 bits of the input register `a`, with
-        // the most significant bits of `a` affecting the last values of output.
-        //
-        // Conversely, the first octal digit of `a` contributes to the final 4 output values (0 to 3 from the end).
-        // First and second octal digits of `a` may contribute to output values 1 to 4 from the end, etc.
-        //
-        // We therefore need to find the first octal digit of `a` before we try to find the second digit,
-        // and in general discover the most significant digits first. This also helps ensure that we find
-        // the lowest possible solution value.
-
-        octal_a[correct_octal_digits] = (counter >> 9) % 8;
-        octal_a[correct_octal_digits + 1] = (counter >> 6) % 8;
-        octal_a[correct_octal_digits + 2] = (counter >> 3) % 8;
-        octal_a[correct_octal_digits + 3] = counter % 8;
-
-        // We need a non-zero value in most significant digit to get correct length output
-        if octal_a[0] == 0 {
-            octal_a[0] = 1;
-        }
-
-        let a = octal_a.iter().fold(0i64, |a, n| (a << 3) + n);
-
-        let output = run((a, registers.1, registers.2), program);
-        if output == *program {
-            return a
-        }
+/// Finds the lowest value of register `a` that makes the program output a copy of itself.
+///
+/// Programs in this family are a single loop that shifts `a` right by a fixed number of bits on
+/// each iteration, so each output value depends only on the bits of `a` that remain. The last
+/// output value depends only on the most significant chunk of `a`, so we search backwards from the
+/// last output value, fixing one chunk of `a` at a time and backtracking when no chunk value
+/// reproduces the remainder of the program.
+///
+/// Returns `None` if the program is not of this form, if no value of `a` produces the program, or
+/// if the program is too long for such a value to fit in register `a`.
+fn find_self_output(registers: (i64, i64, i64), program: &[i64]) -> Option<i64> {
+    let shift = loop_shift(program)?;
+    if shift as usize * program.len() >= i64::BITS as usize {
+        return None;
+    }
+    search_chunks(registers, program, shift, 0, program.len())
+}
 
-        // Check if we found any more digits of correct output at the end. Each octal digit can
-        // contribute towards 4 values of output.
-        let output_rev: Vec<i64> = output.iter().rev().copied().collect();
-        if output_rev[correct_octal_digits..=correct_octal_digits + 3] == program_rev[correct_octal_digits..=correct_octal_digits + 3] {
-            correct_octal_digits += 1;
-            counter = 0;
-        } else {
-            counter += 1                                    ;
-        }
+fn search_chunks(registers: (i64, i64, i64), program: &[i64], shift: i64, a: i64, matched_from: usize) -> Option<i64> {
+    if matched_from == 0 {
+        return Some(a);
+    }
+    let next_from = matched_from - 1;
+    (0..1 << shift)
+        .map(|chunk| (a << shift) | chunk)
+        .filter(|&candidate| run((candidate, registers.1, registers.2), program) == program[next_from..])
+        .find_map(|candidate| search_chunks(registers, program, shift, candidate, next_from))
+}
 
+/// Returns the number of bits of register `a` consumed per loop iteration, if the program is a
+/// single loop back to the start that shifts `a` by a literal amount exactly once.
+fn loop_shift(program: &[i64]) -> Option<i64> {
+    let instructions: Vec<(i64, i64)> = program.chunks(2)
+        .map(|chunk| (chunk[0], *chunk.get(1).unwrap_or(&0)))
+        .collect();
+    let (&last, body) = instructions.split_last()?;
+    if last != (JNZ, 0) || body.iter().any(|&(instruction, _)| instruction == JNZ) {
+        return None;
+    }
+    let shifts: Vec<i64> = body.iter()
+        .filter(|&&(instruction, _)| instruction == ADV)
+        .map(|&(_, arg)| arg)
+        .collect();
+    match shifts[..] {
+        [shift @ 1..=3] => Some(shift),
+        _ => None,
     }
 }
 
@@ -141,4 +190,34 @@ Program: (.*)").unwrap();
         (a.parse().unwrap(), b.parse().unwrap(), c.parse().unwrap()),
         program.split(",").map(|p| p.parse().unwrap()).collect()
     )
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_self_output_for_example() {
+        let (registers, program) = parse_input("Register A: 2024\nRegister B: 0\nRegister C: 0\n\nProgram: 0,3,5,4,3,0");
+        assert_eq!(find_self_output(registers, &program), Some(117440));
+    }
+
+    #[test]
+    fn no_self_output_when_program_cannot_be_reproduced() {
+        let (registers, program) = parse_input("Register A: 0\nRegister B: 0\nRegister C: 0\n\nProgram: 0,3,5,0,3,0");
+        assert_eq!(find_self_output(registers, &program), None);
+    }
+
+    #[test]
+    fn no_self_output_when_register_a_would_overflow() {
+        // adv 3; out a; eight bxl 0; jnz 0 outputs 22 values, which would need 66 bits of a
+        let program = [0, 3, 5, 4, 1, 0, 1, 0, 1, 0, 1, 0, 1, 0, 1, 0, 1, 0, 1, 0, 3, 0];
+        assert_eq!(find_self_output((0, 0, 0), &program), None);
+    }
+
+    #[test]
+    fn no_self_output_without_single_shifting_loop() {
+        let (registers, program) = parse_input("Register A: 0\nRegister B: 0\nRegister C: 0\n\nProgram: 5,4,3,0");
+        assert_eq!(find_self_output(registers, &program), None);
+    }
+}
@@ -0,0 +1,276 @@
+use std::fmt;
+use std::rc::Rc;
+
+use crate::{loop_shift, ADV, BDV, BST, BXC, BXL, CDV, JNZ, OUT};
+
+const WORD_BITS: usize = 64;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Var {
+    A(usize),
+    B(usize),
+    C(usize),
+}
+
+/// A boolean expression over the bits of the registers at the start of a loop iteration.
+#[derive(Debug, PartialEq, Eq)]
+pub enum Expr {
+    Const(bool),
+    Var(Var),
+    Not(Rc<Expr>),
+    Xor(Rc<Expr>, Rc<Expr>),
+    /// `if .0 { .1 } else { .2 }`, produced by shifting by an amount that is not constant
+    Ite(Rc<Expr>, Rc<Expr>, Rc<Expr>),
+}
+
+type Bit = Rc<Expr>;
+type Word = Vec<Bit>;
+
+impl Expr {
+    pub fn eval(&self, var: &impl Fn(Var) -> bool) -> bool {
+        match self {
+            Expr::Const(value) => *value,
+            Expr::Var(v) => var(*v),
+            Expr::Not(x) => !x.eval(var),
+            Expr::Xor(x, y) => x.eval(var) ^ y.eval(var),
+            Expr::Ite(cond, then, otherwise) => if cond.eval(var) { then.eval(var) } else { otherwise.eval(var) },
+        }
+    }
+
+    fn any_var(&self, predicate: &impl Fn(Var) -> bool) -> bool {
+        match self {
+            Expr::Const(_) => false,
+            Expr::Var(v) => predicate(*v),
+            Expr::Not(x) => x.any_var(predicate),
+            Expr::Xor(x, y) => x.any_var(predicate) || y.any_var(predicate),
+            Expr::Ite(cond, then, otherwise) =>
+                cond.any_var(predicate) || then.any_var(predicate) || otherwise.any_var(predicate),
+        }
+    }
+
+    fn as_const(&self) -> Option<bool> {
+        match self {
+            Expr::Const(value) => Some(*value),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Var {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Var::A(i) => write!(f, "a{i}"),
+            Var::B(i) => write!(f, "b{i}"),
+            Var::C(i) => write!(f, "c{i}"),
+        }
+    }
+}
+
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Expr::Const(value) => write!(f, "{}", *value as u8),
+            Expr::Var(v) => write!(f, "{v}"),
+            Expr::Not(x) => write!(f, "!{x}"),
+            Expr::Xor(x, y) => write!(f, "({x} ^ {y})"),
+            Expr::Ite(cond, then, otherwise) => write!(f, "({cond} ? {then} : {otherwise})"),
+        }
+    }
+}
+
+fn constant(value: bool) -> Bit {
+    Rc::new(Expr::Const(value))
+}
+
+fn not(x: &Bit) -> Bit {
+    match x.as_ref() {
+        Expr::Const(value) => constant(!value),
+        Expr::Not(inner) => inner.clone(),
+        _ => Rc::new(Expr::Not(x.clone())),
+    }
+}
+
+fn xor(x: &Bit, y: &Bit) -> Bit {
+    match (x.as_const(), y.as_const()) {
+        (Some(false), _) => y.clone(),
+        (_, Some(false)) => x.clone(),
+        (Some(true), _) => not(y),
+        (_, Some(true)) => not(x),
+        _ if x == y => constant(false),
+        _ => Rc::new(Expr::Xor(x.clone(), y.clone())),
+    }
+}
+
+fn ite(cond: &Bit, then: &Bit, otherwise: &Bit) -> Bit {
+    match (cond.as_const(), then.as_const(), otherwise.as_const()) {
+        (Some(true), _, _) => then.clone(),
+        (Some(false), _, _) => otherwise.clone(),
+        (_, Some(true), Some(false)) => cond.clone(),
+        (_, Some(false), Some(true)) => not(cond),
+        _ if then == otherwise => then.clone(),
+        _ => Rc::new(Expr::Ite(cond.clone(), then.clone(), otherwise.clone())),
+    }
+}
+
+fn literal(value: i64) -> Word {
+    (0..WORD_BITS).map(|i| constant((value >> i) & 1 == 1)).collect()
+}
+
+fn variable(var: fn(usize) -> Var) -> Word {
+    (0..WORD_BITS).map(|i| Rc::new(Expr::Var(var(i)))).collect()
+}
+
+fn xor_words(x: &Word, y: &Word) -> Word {
+    x.iter().zip(y).map(|(x, y)| xor(x, y)).collect()
+}
+
+fn low_bits(x: &Word, bits: usize) -> Word {
+    (0..WORD_BITS).map(|i| if i < bits { x[i].clone() } else { constant(false) }).collect()
+}
+
+/// Barrel shifter: for each bit of `amount`, conditionally shift by the corresponding power of two.
+fn shift_right(x: &Word, amount: &Word) -> Word {
+    let mut result = x.clone();
+    for (stage, amount_bit) in amount.iter().enumerate() {
+        if amount_bit.as_const() == Some(false) {
+            continue;
+        }
+        let distance = 1usize.checked_shl(stage as u32).unwrap_or(usize::MAX);
+        result = (0..WORD_BITS)
+            .map(|i| {
+                let shifted = i.checked_add(distance)
+                    .and_then(|j| result.get(j).cloned())
+                    .unwrap_or_else(|| constant(false));
+                ite(amount_bit, &shifted, &result[i])
+            })
+            .collect();
+    }
+    result
+}
+
+/// The output values of one iteration of a program loop, as 3-bit expressions (least significant
+/// bit first) over the register values at the start of the iteration.
+pub struct Iteration {
+    pub outputs: Vec<[Bit; 3]>,
+}
+
+impl Iteration {
+    /// Symbolically executes the loop body of a program that is a single loop back to the start.
+    pub fn analyse(program: &[i64]) -> Option<Iteration> {
+        loop_shift(program)?;
+        let (mut a, mut b, mut c) = (variable(Var::A), variable(Var::B), variable(Var::C));
+        let mut outputs = vec![];
+        for instruction in program.chunks(2) {
+            let (opcode, arg) = (instruction[0], *instruction.get(1).unwrap_or(&0));
+            let combo = |a: &Word, b: &Word, c: &Word| match arg {
+                0..=3 => literal(arg),
+                4 => a.clone(),
+                5 => b.clone(),
+                6 => c.clone(),
+                _ => panic!("Invalid combo operand {arg}"),
+            };
+            match opcode {
+                ADV => a = shift_right(&a, &combo(&a, &b, &c)),
+                BXL => b = xor_words(&b, &literal(arg)),
+                BST => b = low_bits(&combo(&a, &b, &c), 3),
+                JNZ => break,
+                BXC => b = xor_words(&b, &c),
+                OUT => {
+                    let value = combo(&a, &b, &c);
+                    outputs.push([value[0].clone(), value[1].clone(), value[2].clone()]);
+                }
+                BDV => b = shift_right(&a, &combo(&a, &b, &c)),
+                CDV => c = shift_right(&a, &combo(&a, &b, &c)),
+                _ => panic!("Invalid opcode {opcode}"),
+            }
+        }
+        Some(Iteration { outputs })
+    }
+
+    /// True if the outputs are fully determined by register `a`, so that values of `b` and `c`
+    /// left over from the previous iteration make no difference.
+    pub fn depends_only_on_a(&self) -> bool {
+        !self.outputs.iter().flatten().any(|bit| bit.any_var(&|v| !matches!(v, Var::A(_))))
+    }
+
+    fn output_values(&self, a: i64) -> impl Iterator<Item = i64> + '_ {
+        let var = move |v| match v {
+            Var::A(i) => i < WORD_BITS && (a >> i) & 1 == 1,
+            _ => false,
+        };
+        self.outputs.iter()
+            .map(move |bits| bits.iter().rev().fold(0, |value, bit| (value << 1) | bit.eval(&var) as i64))
+    }
+}
+
+impl fmt::Display for Iteration {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (n, bits) in self.outputs.iter().enumerate() {
+            for (i, bit) in bits.iter().enumerate() {
+                writeln!(f, "out{n}[{i}] = {bit}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Finds the lowest value of register `a` that makes the program output a copy of itself, by
+/// evaluating the symbolic output expressions of the loop body rather than running the program.
+pub fn solve_self_output(program: &[i64]) -> Option<i64> {
+    let shift = loop_shift(program)? as usize;
+    let iteration = Iteration::analyse(program)?;
+    let per_iteration = iteration.outputs.len();
+    if !iteration.depends_only_on_a() || per_iteration == 0 || !program.len().is_multiple_of(per_iteration) {
+        return None;
+    }
+    let iterations = program.len() / per_iteration;
+    if shift * iterations >= WORD_BITS {
+        return None;
+    }
+    solve_chunks(&iteration, program, shift, iterations, 0, iterations)
+}
+
+fn solve_chunks(iteration: &Iteration, program: &[i64], shift: usize, iterations: usize, a: i64, solved_from: usize) -> Option<i64> {
+    if solved_from == 0 {
+        return Some(a);
+    }
+    let i = solved_from - 1;
+    let expected = &program[i * iteration.outputs.len()..(i + 1) * iteration.outputs.len()];
+    // The loop only runs for every iteration if the most significant chunk is non-zero
+    let first_chunk = if i == iterations - 1 && iterations > 1 { 1 } else { 0 };
+    (first_chunk..1 << shift)
+        .map(|chunk| a | (chunk << (shift * i)))
+        .filter(|&candidate| iteration.output_values(candidate >> (shift * i)).eq(expected.iter().copied()))
+        .find_map(|candidate| solve_chunks(iteration, program, shift, iterations, candidate, i))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{find_self_output, parse_input};
+
+    #[test]
+    fn derives_output_formula_for_example() {
+        let (_, program) = parse_input("Register A: 2024\nRegister B: 0\nRegister C: 0\n\nProgram: 0,3,5,4,3,0");
+        let iteration = Iteration::analyse(&program).unwrap();
+        assert_eq!(iteration.to_string(), "out0[0] = a3\nout0[1] = a4\nout0[2] = a5\n");
+        assert_eq!(solve_self_output(&program), Some(117440));
+    }
+
+    #[test]
+    fn matches_interpreter_search() {
+        // A typical puzzle program: bst a; bxl 2; cdv b; bxl 7; bxc; adv 3; out b; jnz 0
+        let (registers, program) = parse_input("Register A: 41644071\nRegister B: 0\nRegister C: 0\n\nProgram: 2,4,1,2,7,5,1,7,4,4,0,3,5,5,3,0");
+        let iteration = Iteration::analyse(&program).unwrap();
+        assert!(iteration.depends_only_on_a());
+        let a = solve_self_output(&program);
+        assert!(a.is_some());
+        assert_eq!(a, find_self_output(registers, &program));
+    }
+
+    #[test]
+    fn no_solution_when_output_depends_on_previous_iteration() {
+        // bxl 1; out b; adv 3; jnz 0 - b carries over between iterations
+        assert_eq!(solve_self_output(&[1, 1, 5, 5, 0, 3, 3, 0]), None);
+    }
+}
@@ -0,0 +1,117 @@
+use std::collections::HashMap;
+
+use aocutil::coord::Coord;
+use aocutil::direction::{Directions, COMPASS};
+
+/// Disjoint sets of cells, joined by union by size with path halving.
+struct UnionFind {
+    parent: Vec<usize>,
+    size: Vec<usize>,
+}
+
+impl UnionFind {
+    fn new(n: usize) -> UnionFind {
+        UnionFind { parent: (0..n).collect(), size: vec![1; n] }
+    }
+
+    fn find(&mut self, mut i: usize) -> usize {
+        while self.parent[i] != i {
+            self.parent[i] = self.parent[self.parent[i]];
+            i = self.parent[i];
+        }
+        i
+    }
+
+    fn union(&mut self, a: usize, b: usize) {
+        let (mut a, mut b) = (self.find(a), self.find(b));
+        if a == b {
+            return;
+        }
+        if self.size[a] < self.size[b] {
+            (a, b) = (b, a);
+        }
+        self.parent[b] = a;
+        self.size[a] += self.size[b];
+    }
+}
+
+/// Finds the index of the first obstacle in `obstacles` that, once it and all those before it have
+/// been placed in a `width` by `height` area, leaves no route between `start` and `end`. Returns
+/// `None` if they are still connected after every obstacle has been placed.
+///
+/// Rather than searching for a route after each obstacle, this places them all and then removes
+/// them again in reverse order, joining each freed cell to its open neighbours, until the start and
+/// end fall into the same set. The last obstacle removed is the first that disconnected them.
+pub fn first_disconnecting(width: i64, height: i64, start: Coord, end: Coord, obstacles: &[Coord]) -> Option<usize> {
+    let in_bounds = |(x, y): Coord| x >= 0 && y >= 0 && x < width && y < height;
+    assert!(in_bounds(start) && in_bounds(end), "Start and end must be within the area");
+    let index = |(x, y): Coord| (y * width + x) as usize;
+
+    // Only the first obstacle to land on a cell blocks it
+    let mut placed: HashMap<Coord, usize> = HashMap::new();
+    for (i, &obstacle) in obstacles.iter().enumerate().filter(|&(_, &c)| in_bounds(c)) {
+        placed.entry(obstacle).or_insert(i);
+    }
+
+    let mut sets = UnionFind::new((width * height) as usize);
+    let mut open = vec![true; sets.parent.len()];
+    for &obstacle in placed.keys() {
+        open[index(obstacle)] = false;
+    }
+    let free = |sets: &mut UnionFind, open: &mut [bool], coord: Coord| {
+        open[index(coord)] = true;
+        for neighbour in COMPASS.directions().iter().map(|d| d.step(coord)) {
+            if in_bounds(neighbour) && open[index(neighbour)] {
+                sets.union(index(coord), index(neighbour));
+            }
+        }
+    };
+    for y in 0..height {
+        for x in 0..width {
+            if open[index((x, y))] {
+                free(&mut sets, &mut open, (x, y));
+            }
+        }
+    }
+
+    let connected = |sets: &mut UnionFind, open: &[bool]| {
+        open[index(start)] && open[index(end)] && sets.find(index(start)) == sets.find(index(end))
+    };
+    if connected(&mut sets, &open) {
+        return None;
+    }
+    for (i, &obstacle) in obstacles.iter().enumerate().rev() {
+        if placed.get(&obstacle) != Some(&i) {
+            continue;
+        }
+        free(&mut sets, &mut open, obstacle);
+        if connected(&mut sets, &open) {
+            return Some(i);
+        }
+    }
+    unreachable!("An area without obstacles is always connected")
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::parse_input;
+
+    #[test]
+    fn finds_first_blocking_byte() {
+        let bytes = parse_input(&fs::read_to_string("test_input.txt").unwrap());
+        let blocking = first_disconnecting(7, 7, (0, 0), (6, 6), &bytes).unwrap();
+        assert_eq!(bytes[blocking], (6, 1));
+        assert_eq!(first_disconnecting(7, 7, (0, 0), (6, 6), &bytes[..blocking]), None);
+    }
+
+    #[test]
+    fn handles_repeated_and_endpoint_obstacles() {
+        let wall = [(1, 0), (1, 0), (1, 1), (1, 2)];
+        assert_eq!(first_disconnecting(3, 3, (0, 0), (2, 2), &wall), Some(3));
+        assert_eq!(first_disconnecting(3, 3, (0, 0), (2, 2), &[(2, 2)]), Some(0));
+        assert_eq!(first_disconnecting(3, 3, (0, 0), (2, 2), &[(5, 5), (0, 1)]), None);
+    }
+}
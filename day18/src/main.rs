@@ -1,12 +1,25 @@
+use std::env;
+
 use regex::Regex;
 use aocutil::coord::Coord;
 use aocutil::direction::{COMPASS, Directions};
 use aocutil::grid::Grid;
 use pathfinding::prelude::bfs;
 
+use connectivity::first_disconnecting;
+use timed::fastest_route;
+
+mod connectivity;
+mod timed;
+
 const DAY: u8 = 18;
 
+/// Usage: `day18 [timed [FALLEN]]`
+///
+/// `timed` finds the fastest route while the bytes keep falling, one per step, after the first
+/// `FALLEN` (1024 by default) have landed.
 fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
     let input = aocutil::load_input(DAY);
     let byte_coords = parse_input(&input);
 
@@ -14,26 +27,28 @@ fn main() {
     let start = (0, 0);
     let end = (70, 70);
 
-    let mut grid = Grid::new_with_coords(byte_coords.iter().take(part1_ticks), '#');
-    let part1= shortest_route(&grid, start, end).unwrap();
-    println!("Part 1: {}", part1.len());
-
-    let part2 = add_coords_until_no_route(&mut grid, start, end, byte_coords.clone().into_iter().skip(part1_ticks).collect());
-    println!("Part 2: {},{}", part2.0, part2.1);
-}
-
-fn add_coords_until_no_route(grid: &mut Grid, start: Coord, end: Coord, remaining_coords: Vec<Coord>) -> Coord {
-    let mut current_route = shortest_route(grid, start, end).unwrap();
-    for coord in remaining_coords {
-        grid.set(coord, '#');
-        if current_route.contains(&coord) {
-            match shortest_route(grid, (0, 0), end) {
-                Some(route) => current_route = route,
-                None => return coord
+    if args.first().is_some_and(|mode| mode == "timed") {
+        let fallen = args.get(1).map_or(part1_ticks, |n| n.parse().expect("Invalid number of fallen bytes"));
+        let mut grid = Grid::new_with_coords(byte_coords.iter().take(fallen), '#');
+        grid.set(start, '.');
+        grid.set(end, '.');
+        match fastest_route(&grid, start, end, &byte_coords[fallen.min(byte_coords.len())..]) {
+            Some(timed) => {
+                println!("Arrival: {}", timed.arrival);
+                println!("Route: {}", timed.route.iter().map(|(x, y)| format!("{x},{y}")).collect::<Vec<_>>().join(" "));
             }
+            None => println!("The falling bytes cut off the exit"),
         }
+        return;
     }
-    panic!()
+
+    let grid = Grid::new_with_coords(byte_coords.iter().take(part1_ticks), '#');
+    let part1= shortest_route(&grid, start, end).unwrap();
+    println!("Part 1: {}", part1.len());
+
+    let part2 = first_disconnecting(end.0 + 1, end.1 + 1, start, end, &byte_coords).expect("No byte blocks the exit");
+    let (x, y) = byte_coords[part2];
+    println!("Part 2: {},{}", x, y);
 }
 
 fn shortest_route(grid: &Grid, start: Coord, end: Coord) -> Option<Vec<Coord>> {
@@ -0,0 +1,89 @@
+use std::collections::{HashMap, VecDeque};
+
+use aocutil::coord::Coord;
+use aocutil::grid::Grid;
+
+use crate::get_next_nodes;
+
+#[derive(Debug, PartialEq)]
+pub struct TimedRoute {
+    /// The number of steps taken to reach the end
+    pub arrival: usize,
+    /// Every cell visited, from the start to the end
+    pub route: Vec<Coord>,
+}
+
+/// Finds the fastest route from `start` to `end` while the `falling` bytes keep landing, one as each
+/// step is taken, on top of those already in the grid. A cell can't be entered once its byte has
+/// landed, including at the very moment it lands. The grid's bounds are the memory space, so it
+/// should span the start and end.
+///
+/// Searches over (cell, time) states breadth first. As bytes never go away, a cell reached later
+/// is never less blocked in than when it was first reached, so only the earliest arrival at each
+/// cell is explored and waiting in place never helps.
+pub fn fastest_route(grid: &Grid, start: Coord, end: Coord, falling: &[Coord]) -> Option<TimedRoute> {
+    let mut lands_at: HashMap<Coord, usize> = HashMap::new();
+    for (i, &byte) in falling.iter().enumerate() {
+        lands_at.entry(byte).or_insert(i + 1);
+    }
+
+    let mut previous: HashMap<Coord, Coord> = HashMap::new();
+    let mut queue = VecDeque::from([(start, 0)]);
+    previous.insert(start, start);
+    while let Some((coord, time)) = queue.pop_front() {
+        if coord == end {
+            let mut route = vec![end];
+            while *route.last().unwrap() != start {
+                route.push(previous[route.last().unwrap()]);
+            }
+            route.reverse();
+            return Some(TimedRoute { arrival: time, route });
+        }
+        for next in get_next_nodes(grid, coord) {
+            let open = lands_at.get(&next).is_none_or(|&landing| landing > time + 1);
+            if open && !previous.contains_key(&next) {
+                previous.insert(next, coord);
+                queue.push_back((next, time + 1));
+            }
+        }
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::parse_input;
+
+    fn empty_space(size: i64) -> Grid {
+        let mut grid = Grid::new();
+        grid.set((0, 0), '.');
+        grid.set((size - 1, size - 1), '.');
+        grid
+    }
+
+    #[test]
+    fn races_falling_bytes() {
+        let bytes = parse_input(&fs::read_to_string("test_input.txt").unwrap());
+        let route = fastest_route(&empty_space(7), (0, 0), (6, 6), &bytes).unwrap();
+        assert_eq!(route.arrival, 12);
+        assert_eq!(route.route.len(), 13);
+        for (time, &cell) in route.route.iter().enumerate().skip(1) {
+            assert!(!bytes[..time].contains(&cell), "{cell:?} blocked at {time}");
+        }
+    }
+
+    #[test]
+    fn slips_past_bytes_before_they_land() {
+        // A corridor along the top row
+        let mut corridor = Grid::new_with_coords([(0, 1), (1, 1), (2, 1), (3, 1), (4, 1)].iter(), '#');
+        corridor.set((0, 0), '.');
+        corridor.set((4, 0), '.');
+        let route = fastest_route(&corridor, (0, 0), (4, 0), &[(4, 1), (4, 1), (2, 0)]).unwrap();
+        assert_eq!(route, TimedRoute { arrival: 4, route: vec![(0, 0), (1, 0), (2, 0), (3, 0), (4, 0)] });
+        assert_eq!(fastest_route(&corridor, (0, 0), (4, 0), &[(4, 1), (2, 0)]), None);
+        assert_eq!(fastest_route(&corridor, (0, 0), (4, 0), &[(4, 0)]), None);
+    }
+}
@@ -0,0 +1,119 @@
+use std::collections::{BTreeMap, HashMap, VecDeque};
+
+use aocutil::coord::Coord;
+use aocutil::grid::Grid;
+
+use crate::successors;
+
+/// The distances from every track cell to the start and to the end, which give the length of the
+/// best race through any cell without it having to lie on a single corridor.
+pub struct Racetrack {
+    from_start: HashMap<Coord, i64>,
+    from_end: HashMap<Coord, i64>,
+    best: i64,
+}
+
+impl Racetrack {
+    /// Measures the track, or returns `None` if the end can't be reached from the start.
+    pub fn new(grid: &Grid, start: Coord, end: Coord) -> Option<Racetrack> {
+        let from_start = distances(grid, start);
+        let from_end = distances(grid, end);
+        let best = *from_start.get(&end)?;
+        Some(Racetrack { from_start, from_end, best })
+    }
+
+    /// The number of picoseconds the race takes without cheating.
+    pub fn best(&self) -> i64 {
+        self.best
+    }
+
+    /// Counts the cheats lasting up to `radius` picoseconds by the time they save. A cheat is
+    /// identified by its start and end positions, so the cheats from a cell are the track cells in
+    /// the diamond of that radius around it.
+    pub fn savings(&self, radius: i64) -> BTreeMap<i64, usize> {
+        let offsets: Vec<(Coord, i64)> = (-radius..=radius)
+            .flat_map(|dy| {
+                let width = radius - dy.abs();
+                (-width..=width).map(move |dx| ((dx, dy), dx.abs() + dy.abs()))
+            })
+            .filter(|&(_, length)| length >= 2)
+            .collect();
+
+        let mut histogram = BTreeMap::new();
+        for (&(x, y), &before) in &self.from_start {
+            for &((dx, dy), length) in &offsets {
+                if let Some(&after) = self.from_end.get(&(x + dx, y + dy)) {
+                    let saving = self.best - (before + length + after);
+                    if saving > 0 {
+                        *histogram.entry(saving).or_insert(0) += 1;
+                    }
+                }
+            }
+        }
+        histogram
+    }
+
+    /// The number of cheats lasting up to `radius` picoseconds that save at least `minimum`.
+    pub fn count_saving_at_least(&self, radius: i64, minimum: i64) -> usize {
+        self.savings(radius).range(minimum..).map(|(_, &count)| count).sum()
+    }
+}
+
+/// Breadth first search for the distance to every track cell reachable from `from`.
+fn distances(grid: &Grid, from: Coord) -> HashMap<Coord, i64> {
+    let mut distances = HashMap::from([(from, 0)]);
+    let mut queue = VecDeque::from([from]);
+    while let Some(coord) = queue.pop_front() {
+        let distance = distances[&coord];
+        for next in successors(grid, &coord) {
+            distances.entry(next).or_insert_with(|| {
+                queue.push_back(next);
+                distance + 1
+            });
+        }
+    }
+    distances
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    fn example() -> Racetrack {
+        let grid = Grid::parse(&fs::read_to_string("test_input.txt").unwrap());
+        Racetrack::new(&grid, grid.find_cell('S').unwrap(), grid.find_cell('E').unwrap()).unwrap()
+    }
+
+    #[test]
+    fn lists_example_savings() {
+        let track = example();
+        assert_eq!(track.best(), 84);
+        let expected = [(2, 14), (4, 14), (6, 2), (8, 4), (10, 2), (12, 3), (20, 1), (36, 1), (38, 1), (40, 1), (64, 1)];
+        assert_eq!(track.savings(2), BTreeMap::from(expected));
+
+        let expected = [
+            (50, 32), (52, 31), (54, 29), (56, 39), (58, 25), (60, 23), (62, 20),
+            (64, 19), (66, 12), (68, 14), (70, 12), (72, 22), (74, 4), (76, 3),
+        ];
+        let savings = track.savings(20);
+        assert_eq!(savings.range(50..).map(|(&saving, &count)| (saving, count)).collect::<Vec<_>>(), expected);
+        assert_eq!(track.count_saving_at_least(20, 76), 3);
+    }
+
+    #[test]
+    fn handles_open_tracks() {
+        // Two equally short routes around the block at the top left. Only the route down the left
+        // side passes (1, 3), where cutting through the wall below saves 8 picoseconds, so counting
+        // cheats along a single route would miss it if the route went across the top instead.
+        let grid = Grid::parse("#######\n#S..###\n#.#.###\n#.....#\n#####.#\n#.....#\n#.#####\n#...E##\n#######");
+        let track = Racetrack::new(&grid, (1, 1), (4, 7)).unwrap();
+        assert_eq!(track.best(), 17);
+        assert_eq!(track.savings(2), BTreeMap::from([(2, 2), (4, 2), (6, 2), (8, 1)]));
+        assert_eq!(track.count_saving_at_least(2, 8), 1);
+
+        let walled = Grid::parse("#####\n#S#E#\n#####");
+        assert!(Racetrack::new(&walled, (1, 1), (3, 1)).is_none());
+    }
+}
@@ -1,36 +1,40 @@
-use aocutil::coord::{Coord, manhattan_distance};
+use std::env;
+
+use aocutil::coord::Coord;
 use aocutil::direction::{COMPASS, Directions};
 use aocutil::grid::Grid;
 
+use cheats::Racetrack;
+
+mod cheats;
+
 const DAY: u8 = 20;
 
+/// Usage: `day20 [histogram RADIUS]`
+///
+/// `histogram` lists how many cheats lasting up to `RADIUS` picoseconds save each amount of time.
 fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
     let input = aocutil::load_input(DAY);
     let grid = Grid::parse(&input);
 
     let start = grid.find_cell('S').unwrap();
     let end = grid.find_cell('E').unwrap();
-    let route = route(&grid, start, end);
-
-    println!("Part 1: {}", shortcuts_saving_at_least_100(&route, 2));
-    println!("Part 2: {}", shortcuts_saving_at_least_100(&route, 20));
-}
+    let track = Racetrack::new(&grid, start, end).expect("The end cannot be reached");
 
-fn shortcuts_saving_at_least_100(route: &[Coord], max_shortcut_distance: i64) -> i64 {
-    let mut count = 0;
-    for (d0, &c0) in route.iter().enumerate() {
-        for (route_distance, &c1) in route[d0..].iter().enumerate() {
-            let shortcut_distance = manhattan_distance(c0, c1);
-            if shortcut_distance <= max_shortcut_distance && route_distance as i64 - shortcut_distance >= 100 {
-                count += 1;
+    match &args[..] {
+        [] => {
+            println!("Part 1: {}", track.count_saving_at_least(2, 100));
+            println!("Part 2: {}", track.count_saving_at_least(20, 100));
+        }
+        [mode, radius] if mode == "histogram" => {
+            println!("The race takes {} picoseconds without cheating", track.best());
+            for (saving, count) in track.savings(radius.parse().expect("Invalid radius")) {
+                println!("{count} cheats save {saving} picoseconds");
             }
         }
+        _ => panic!("Usage: day20 [histogram RADIUS]"),
     }
-    count
-}
-
-fn route(grid: &Grid, start: Coord, end: Coord) -> Vec<Coord> {
-    pathfinding::prelude::bfs(&start, |coord| successors(grid, coord), |p| *p == end).unwrap()
 }
 
 fn successors(grid: &Grid, coord: &Coord) -> Vec<Coord> {
@@ -38,4 +42,4 @@ fn successors(grid: &Grid, coord: &Coord) -> Vec<Coord> {
         .map(|d| d.step(*coord))
         .filter(|&c| grid.is_in_bounds(c) && grid.get_or(c, '.') != '#')
         .collect()
-}
\ No newline at end of file
+}
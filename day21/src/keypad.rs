@@ -0,0 +1,281 @@
+use std::cmp::Reverse;
+use std::collections::{BinaryHeap, HashMap, HashSet};
+
+use aocutil::coord::Coord;
+use aocutil::direction::{Directions, COMPASS};
+use aocutil::grid::Grid;
+
+/// The cost of pressing the button `.1` on a keypad, when its robot's arm is pointing at `.0`,
+/// in terms of button presses by the human at the end of the chain.
+pub type Costs = HashMap<(char, char), i64>;
+
+/// The buttons on a directional keypad that move a robot arm one step.
+pub const DIRECTION_BUTTONS: [(char, &str); 4] = [('^', "N"), ('>', "E"), ('v', "S"), ('<', "W")];
+
+/// A robot arm position and the button last pressed on the keypad controlling it.
+type State = (Coord, char);
+
+pub struct Keypad {
+    buttons: HashMap<char, Coord>,
+}
+
+impl Keypad {
+    /// Parses a keypad layout with one character per button. `.` marks a gap, as does any
+    /// position beyond the end of a shorter row. Robot arms start over the `A` button.
+    pub fn parse(layout: &str) -> Keypad {
+        let buttons = Grid::parse(layout).index_cells("", ".");
+        assert!(buttons.contains_key(&'A'), "Keypad must have an 'A' button");
+        Keypad { buttons }
+    }
+
+    pub fn numeric() -> Keypad {
+        Keypad::parse("789\n456\n123\n.0A")
+    }
+
+    pub fn directional() -> Keypad {
+        Keypad::parse(".^A\n<v>")
+    }
+
+    pub fn buttons(&self) -> impl Iterator<Item = char> + '_ {
+        self.buttons.keys().copied()
+    }
+
+    pub fn position(&self, button: char) -> Option<Coord> {
+        self.buttons.get(&button).copied()
+    }
+
+    pub fn button_at(&self, coord: Coord) -> Option<char> {
+        self.buttons.iter().find(|(_, &c)| c == coord).map(|(&button, _)| button)
+    }
+
+    /// Works out the cost of every button press on this keypad, given the costs of pressing buttons
+    /// on the directional keypad that controls it.
+    pub fn costs(&self, controller: &Costs) -> Costs {
+        let mut costs = Costs::new();
+        for (&from, &start) in &self.buttons {
+            let moves = self.shortest_moves(start, controller);
+            for &to in self.buttons.keys() {
+                // Buttons that cannot be reached have no cost
+                if let Some((cost, _)) = self.cheapest_press(&moves, to, controller) {
+                    costs.insert((from, to), cost);
+                }
+            }
+        }
+        costs
+    }
+
+    /// Returns the cheapest sequence of buttons to press on the controlling keypad to make this
+    /// keypad's robot move from `from` and press `to`, ending with `A`.
+    pub fn best_presses(&self, from: char, to: char, controller: &Costs) -> Vec<char> {
+        let moves = self.shortest_moves(self.position(from).unwrap(), controller);
+        let (_, mut state) = self.cheapest_press(&moves, to, controller)
+            .unwrap_or_else(|| panic!("Cannot move from '{from}' to '{to}'"));
+        let mut presses = vec!['A'];
+        while let (_, Some(previous)) = moves[&state] {
+            presses.push(state.1);
+            state = previous;
+        }
+        presses.reverse();
+        presses
+    }
+
+    /// Moving from one button to another is a shortest path problem over states of (position on
+    /// this keypad, button last pressed on the controlling keypad), because the cost of each step
+    /// depends on where the controlling robot's arm was left. Every valid ordering of moves is
+    /// considered, including detours around gaps, so no assumptions are made about the layout.
+    ///
+    /// Returns the cost of reaching each state and the state it was reached from.
+    fn shortest_moves(&self, start: Coord, controller: &Costs) -> HashMap<State, (i64, Option<State>)> {
+        let positions: HashSet<Coord> = self.buttons.values().copied().collect();
+        let mut best = HashMap::new();
+        let mut queue = BinaryHeap::from([Reverse((0, (start, 'A'), None))]);
+        while let Some(Reverse((cost, state, previous))) = queue.pop() {
+            if best.contains_key(&state) {
+                continue;
+            }
+            best.insert(state, (cost, previous));
+            let (position, last) = state;
+            for (button, direction) in DIRECTION_BUTTONS {
+                let next = COMPASS.parse(direction).step(position);
+                if let (true, Some(step)) = (positions.contains(&next), controller.get(&(last, button))) {
+                    queue.push(Reverse((cost + step, (next, button), Some(state))));
+                }
+            }
+        }
+        best
+    }
+
+    /// The cheapest way to finish at button `to` and press it, with its total cost.
+    fn cheapest_press(&self, moves: &HashMap<State, (i64, Option<State>)>, to: char, controller: &Costs) -> Option<(i64, State)> {
+        let end = self.position(to)?;
+        moves.iter()
+            .filter(|&(&(position, _), _)| position == end)
+            .filter_map(|(&(position, last), &(cost, _))| Some((cost + controller.get(&(last, 'A'))?, (position, last))))
+            .min()
+    }
+}
+
+/// The cost of each button press for a human typing directly on a keypad.
+pub fn human_costs(keypad: &Keypad) -> Costs {
+    keypad.buttons()
+        .flat_map(|from| keypad.buttons().map(move |to| ((from, to), 1)))
+        .collect()
+}
+
+/// Works out the cost of every button press on the first keypad in a chain, where each keypad is
+/// operated by a robot controlled from the next keypad, and a human types on the last one.
+pub fn chain_costs(chain: &[Keypad]) -> Costs {
+    chain_cost_levels(chain).swap_remove(0)
+}
+
+/// The costs of button presses on every keypad in the chain.
+fn chain_cost_levels(chain: &[Keypad]) -> Vec<Costs> {
+    let (human, robots) = chain.split_last().expect("Chain must have at least one keypad");
+    let mut levels = vec![human_costs(human)];
+    for keypad in robots.iter().rev() {
+        levels.push(keypad.costs(levels.last().unwrap()));
+    }
+    levels.reverse();
+    levels
+}
+
+/// Returns one of the shortest sequences of buttons that the human can press to type `code` on
+/// the first keypad in the chain. The sequence grows exponentially with the length of the chain.
+pub fn keypresses_for_code(code: &str, chain: &[Keypad]) -> String {
+    let levels = chain_cost_levels(chain);
+    let mut presses: Vec<char> = code.chars().collect();
+    for (level, keypad) in chain[..chain.len() - 1].iter().enumerate() {
+        let mut from = 'A';
+        presses = presses.iter()
+            .flat_map(|&to| keypad.best_presses(std::mem::replace(&mut from, to), to, &levels[level + 1]))
+            .collect();
+    }
+    presses.into_iter().collect()
+}
+
+/// A robot pointed its arm at a gap or off the edge of its keypad.
+#[derive(Debug, PartialEq)]
+pub struct RobotPanic {
+    /// The index in the chain of the keypad that the robot was operating
+    pub keypad: usize,
+    /// The index of the human's keypress that caused the panic
+    pub keypress: usize,
+}
+
+#[derive(Debug, PartialEq)]
+pub struct Simulation {
+    /// The buttons pressed on each keypad in the chain, with the human's keypresses last
+    pub presses: Vec<String>,
+    pub panic: Option<RobotPanic>,
+}
+
+/// Feeds the human's keypresses through the chain of robots, recording what each robot presses.
+/// Stops at the first keypress that makes a robot panic.
+pub fn simulate(chain: &[Keypad], keypresses: &str) -> Simulation {
+    let human = chain.len() - 1;
+    let mut arms: Vec<Coord> = chain[..human].iter().map(|keypad| keypad.position('A').unwrap()).collect();
+    let mut presses = vec![String::new(); chain.len()];
+    for (keypress, button) in keypresses.chars().enumerate() {
+        if chain[human].position(button).is_none() {
+            return Simulation { presses, panic: Some(RobotPanic { keypad: human, keypress }) };
+        }
+        presses[human].push(button);
+        let mut button = button;
+        for level in (0..human).rev() {
+            if button == 'A' {
+                button = chain[level].button_at(arms[level]).unwrap();
+                presses[level].push(button);
+                continue;
+            }
+            let moved = DIRECTION_BUTTONS.iter()
+                .find(|&&(b, _)| b == button)
+                .map(|&(_, direction)| COMPASS.parse(direction).step(arms[level]))
+                .filter(|&next| chain[level].button_at(next).is_some());
+            match moved {
+                Some(next) => arms[level] = next,
+                None => return Simulation { presses, panic: Some(RobotPanic { keypad: level, keypress }) },
+            }
+            break;
+        }
+    }
+    Simulation { presses, panic: None }
+}
+
+/// The number of buttons the human must press to type `code` on the first keypad in the chain.
+pub fn button_presses_for_code(code: &str, costs: &Costs) -> i64 {
+    ['A'].into_iter().chain(code.chars()).zip(code.chars())
+        .map(|(from, to)| *costs.get(&(from, to)).unwrap_or_else(|| panic!("Cannot move from '{from}' to '{to}'")))
+        .sum()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::puzzle_chain;
+
+    #[test]
+    fn counts_example_presses() {
+        let costs = chain_costs(&puzzle_chain(2));
+        let presses: Vec<i64> = ["029A", "980A", "179A", "456A", "379A"].iter()
+            .map(|code| button_presses_for_code(code, &costs))
+            .collect();
+        assert_eq!(presses, vec![68, 60, 68, 64, 64]);
+    }
+
+    #[test]
+    fn detours_around_several_gaps() {
+        // Going from A to B means going down, along and back up again
+        let chain = [Keypad::parse("A.B\nCDE"), Keypad::directional()];
+        let costs = chain_costs(&chain);
+        assert_eq!(costs[&('A', 'B')], 5);
+        assert_eq!(costs[&('B', 'A')], 5);
+        assert_eq!(costs[&('C', 'E')], 3);
+
+        let chain = [Keypad::parse("1.2\n3.4\n567\n.A."), Keypad::directional()];
+        let costs = chain_costs(&chain);
+        assert_eq!(button_presses_for_code("12", &costs), 12);
+        assert_eq!(button_presses_for_code("A", &costs), 1);
+    }
+
+    #[test]
+    fn simulates_example_keypresses() {
+        let keypresses = "<vA<AA>>^AvAA<^A>A<v<A>>^AvA^A<vA>^A<v<A>^A>AAvA^A<v<A>A>^AAAvA<^A>A";
+        let simulation = simulate(&puzzle_chain(2), keypresses);
+        assert_eq!(simulation.panic, None);
+        assert_eq!(simulation.presses, vec![
+            "029A",
+            "<A^A>^^AvvvA",
+            "v<<A>>^A<A>AvA<^AA>A<vAAA>^A",
+            keypresses,
+        ]);
+    }
+
+    #[test]
+    fn reports_robot_panics() {
+        // The first robot's arm moves from A to ^, then off into the gap
+        let simulation = simulate(&puzzle_chain(1), "<A<A");
+        assert_eq!(simulation.panic, Some(RobotPanic { keypad: 1, keypress: 2 }));
+        assert_eq!(simulation.presses, vec!["", "^", "<A<"]);
+        let simulation = simulate(&puzzle_chain(0), "<<A");
+        assert_eq!(simulation.panic, Some(RobotPanic { keypad: 0, keypress: 1 }));
+    }
+
+    #[test]
+    fn reconstructs_keypresses_matching_counts() {
+        for robots in 0..=3 {
+            let chain = puzzle_chain(robots);
+            let costs = chain_costs(&chain);
+            for code in ["029A", "980A", "179A", "456A", "379A"] {
+                let keypresses = keypresses_for_code(code, &chain);
+                assert_eq!(keypresses.len() as i64, button_presses_for_code(code, &costs));
+                let simulation = simulate(&chain, &keypresses);
+                assert_eq!(simulation.panic, None);
+                assert_eq!(simulation.presses[0], code);
+            }
+        }
+        let chain = [Keypad::parse("1.2\n3.4\n567\n.A."), Keypad::directional(), Keypad::directional()];
+        let keypresses = keypresses_for_code("12A", &chain);
+        assert_eq!(simulate(&chain, &keypresses).presses[0], "12A");
+        assert_eq!(keypresses.len() as i64, button_presses_for_code("12A", &chain_costs(&chain)));
+    }
+}
@@ -1,51 +1,75 @@
-use std::collections::HashMap;
+use std::env;
+
+use market::Market;
+
+mod market;
 
 const DAY: u8 = 22;
 
-type DeltaPattern = (i64, i64, i64, i64);
+const MASK: u32 = (1 << 24) - 1;
 
+/// Usage: `day22 [report N [WINDOW STEPS MODULUS]]`
+///
+/// With `report`, lists the `N` patterns of price changes that buy the most bananas and the price
+/// each buyer, named by their initial secret number, sells at. Optionally looks for `WINDOW`
+/// changes over `STEPS` secret numbers, priced by the secret modulo `MODULUS`.
 fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
     let input = aocutil::load_input(DAY);
     let values = parse_input(&input);
 
-    let part1: i64 = values.iter()
-        .flat_map(|&n| random_sequence(n).skip(2000).take(1))
-        .sum();
-    println!("Part 1: {}", part1);
-
-    let total_pattern_scores = values.iter()
-        .map(|&n| pattern_prices_for_monkey(n))
-        .fold(HashMap::new(), |acc, m| accumulate(acc, &m));
-    println!("Part 2: {}", *total_pattern_scores.values().max().unwrap());
+    match &args[..] {
+        [] => {
+            let (part1, totals) = Market::standard().analyse(&values);
+            println!("Part 1: {}", part1);
+            println!("Part 2: {}", totals.best());
+        }
+        [mode, rest @ ..] if mode == "report" => {
+            let numbers: Vec<usize> = rest.iter()
+                .map(|arg| arg.parse().unwrap_or_else(|_| panic!("Invalid argument '{arg}'")))
+                .collect();
+            match numbers[..] {
+                [n] => report(&Market::standard(), &values, n),
+                [n, window, steps, modulus] => {
+                    let modulus = u32::try_from(modulus).ok().filter(|&m| m > 0).expect("Modulus must be positive and fit in 32 bits");
+                    let price = move |secret| secret % modulus;
+                    report(&Market { window, steps, prices: modulus, price: &price }, &values, n);
+                }
+                _ => panic!("Usage: day22 [report N [WINDOW STEPS MODULUS]]"),
+            }
+        }
+        _ => panic!("Usage: day22 [report N [WINDOW STEPS MODULUS]]"),
+    }
 }
 
-fn accumulate(mut acc: HashMap<DeltaPattern, i64>, n: &HashMap<DeltaPattern, i64>) -> HashMap<DeltaPattern, i64> {
-    n.iter().for_each(|(&pattern, &price)| *acc.entry(pattern).or_default() += price);
-    acc
+fn report(market: &Market, seeds: &[u32], n: usize) {
+    for pattern in market.top_patterns(seeds, n) {
+        println!("{:?}: {} bananas from {} buyers", pattern.changes, pattern.total, pattern.sales.iter().flatten().count());
+        for (seed, price) in seeds.iter().zip(&pattern.sales) {
+            if let Some(price) = price {
+                println!("  buyer {seed} sells at {price}");
+            }
+        }
+    }
 }
 
-fn pattern_prices_for_monkey(seed: i64) -> HashMap<DeltaPattern, i64> {
-    let prices: Vec<i64> = random_sequence(seed).take(2001)
-        .map(|n| n % 10)
-        .collect();
-    prices.windows(5)
-        .map(|p| ((p[1] - p[0], p[2] - p[1], p[3] - p[2], p[4] - p[3]), p[4]))
-        .rev()  // include earliest prices in HashMap
-        .collect()
+fn next_secret(n: u32) -> u32 {
+    let n = ((n << 6) ^ n) & MASK;
+    let n = (n >> 5) ^ n;
+    ((n << 11) ^ n) & MASK
 }
 
-fn random_sequence(seed: i64) -> impl Iterator<Item = i64> {
-    std::iter::successors(
-        Some(seed),
-        move |&n| {
-            let n = ((n * 64) ^ n) % 16777216;
-            let n = ((n / 32) ^ n) % 16777216;
-            let n = ((n * 2048) ^ n) % 16777216;
-            Some(n)
-        },
-    )
+fn parse_input(input: &str) -> Vec<u32> {
+    input.lines().map(|line| line.parse().unwrap()).collect()
 }
 
-fn parse_input(input: &str) -> Vec<i64> {
-    input.lines().map(|line| line.parse().unwrap()).collect()
-}
\ No newline at end of file
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn generates_secret_numbers() {
+        let secrets: Vec<u32> = std::iter::successors(Some(123), |&n| Some(next_secret(n))).skip(1).take(3).collect();
+        assert_eq!(secrets, vec![15887950, 16495136, 527345]);
+    }
+}
@@ -0,0 +1,298 @@
+use std::collections::HashMap;
+use std::thread;
+
+use crate::next_secret;
+
+/// Buyers whose secret numbers are generated side by side, so the compiler can vectorise the steps.
+const LANES: usize = 8;
+
+/// The largest number of patterns that are totalled in flat arrays rather than a map.
+const FLAT_PATTERNS: u64 = 1 << 20;
+
+/// The price of a banana when a buyer has the given secret number.
+pub type PriceFn = dyn Fn(u32) -> u32 + Sync;
+
+fn last_digit(secret: u32) -> u32 {
+    secret % 10
+}
+
+/// How buyers' prices are generated and which patterns of price changes are looked for.
+pub struct Market<'a> {
+    /// The number of consecutive price changes in a pattern
+    pub window: usize,
+    /// The number of new secret numbers each buyer generates
+    pub steps: usize,
+    /// The number of distinct prices: every price must be below this
+    pub prices: u32,
+    pub price: &'a PriceFn,
+}
+
+/// A pattern of price changes that the monkey could look for, and what it earns.
+#[derive(Debug, PartialEq)]
+pub struct PatternReport {
+    pub changes: Vec<i32>,
+    pub total: u64,
+    /// The price at which each buyer sells, or `None` if the pattern never appears
+    pub sales: Vec<Option<u32>>,
+}
+
+impl Market<'static> {
+    /// The puzzle's market: four price changes over 2000 steps, priced by the last digit.
+    pub fn standard() -> Market<'static> {
+        Market { window: 4, steps: 2000, prices: 10, price: &last_digit }
+    }
+}
+
+impl Market<'_> {
+    /// Returns the sum of every buyer's final secret number, and the total bananas bought with every
+    /// pattern of price changes. Buyers are shared out between threads, which each keep their own
+    /// pattern totals until they are added together at the end.
+    pub fn analyse(&self, seeds: &[u32]) -> (i64, Totals) {
+        assert!(self.window > 0, "Pattern must have at least one price change");
+        let threads = thread::available_parallelism().map_or(1, |n| n.get());
+        let chunk_size = seeds.len().div_ceil(threads).max(1);
+        let results: Vec<(i64, Totals)> = thread::scope(|scope| {
+            let handles: Vec<_> = seeds.chunks(chunk_size)
+                .map(|chunk| scope.spawn(|| self.analyse_buyers(chunk)))
+                .collect();
+            handles.into_iter().map(|handle| handle.join().unwrap()).collect()
+        });
+
+        let secret_sum = results.iter().map(|(sum, _)| sum).sum();
+        let totals = results.into_iter()
+            .map(|(_, totals)| totals)
+            .reduce(Totals::merge)
+            .unwrap_or_else(|| Totals::new(self.pattern_count()));
+        (secret_sum, totals)
+    }
+
+    /// The `n` patterns that buy the most bananas, best first, with the price each buyer sells at.
+    /// Ties are broken by the order of the price changes.
+    pub fn top_patterns(&self, seeds: &[u32], n: usize) -> Vec<PatternReport> {
+        let (_, totals) = self.analyse(seeds);
+        let mut patterns: Vec<(u64, u64)> = totals.iter().collect();
+        patterns.sort_by_key(|&(pattern, total)| (std::cmp::Reverse(total), pattern));
+        patterns.into_iter()
+            .take(n)
+            .map(|(pattern, total)| PatternReport {
+                changes: self.decode(pattern),
+                total,
+                sales: seeds.iter().map(|&seed| self.sale_price(seed, pattern)).collect(),
+            })
+            .collect()
+    }
+
+    /// Each price change is offset to be non-negative, and a pattern is the changes written as the
+    /// digits of a number in base `2 * prices - 1`.
+    fn base(&self) -> u64 {
+        2 * self.prices as u64 - 1
+    }
+
+    fn pattern_count(&self) -> u64 {
+        u32::try_from(self.window).ok()
+            .and_then(|window| self.base().checked_pow(window))
+            .unwrap_or_else(|| panic!("A pattern of {} changes is too long to index", self.window))
+    }
+
+    fn decode(&self, mut pattern: u64) -> Vec<i32> {
+        let mut changes = vec![0; self.window];
+        for change in changes.iter_mut().rev() {
+            *change = (pattern % self.base()) as i32 - (self.prices as i32 - 1);
+            pattern /= self.base();
+        }
+        changes
+    }
+
+    /// Every complete pattern of price changes in a buyer's prices, with the price after it.
+    fn patterns(&self, prices: impl Iterator<Item = u32>) -> impl Iterator<Item = (u64, u32)> {
+        let (base, count, prices_count, window) = (self.base(), self.pattern_count(), self.prices, self.window);
+        let mut pattern = 0;
+        let mut previous = None;
+        prices.enumerate().filter_map(move |(step, price)| {
+            assert!(price < prices_count, "Price {price} is out of range");
+            let last = previous.replace(price)?;
+            // Drop the oldest change before shifting, so the pattern never exceeds the count
+            pattern = pattern % (count / base) * base + (price + prices_count - 1 - last) as u64;
+            (step >= window).then_some((pattern, price))
+        })
+    }
+
+    fn buyer_prices(&self, seed: u32) -> impl Iterator<Item = u32> + '_ {
+        std::iter::successors(Some(seed), |&secret| Some(next_secret(secret)))
+            .take(self.steps + 1)
+            .map(self.price)
+    }
+
+    fn sale_price(&self, seed: u32, pattern: u64) -> Option<u32> {
+        self.patterns(self.buyer_prices(seed))
+            .find(|&(p, _)| p == pattern)
+            .map(|(_, price)| price)
+    }
+
+    fn analyse_buyers(&self, seeds: &[u32]) -> (i64, Totals) {
+        let mut secret_sum = 0;
+        let mut totals = Totals::new(self.pattern_count());
+        let mut secrets = vec![[0u32; LANES]; self.steps + 1];
+
+        for (batch_index, batch) in seeds.chunks(LANES).enumerate() {
+            secrets[0][..batch.len()].copy_from_slice(batch);
+            for step in 1..=self.steps {
+                secrets[step] = secrets[step - 1].map(next_secret);
+            }
+            secret_sum += secrets[self.steps][..batch.len()].iter().map(|&secret| secret as i64).sum::<i64>();
+
+            for lane in 0..batch.len() {
+                // Buyers are numbered from 1 so that a buyer never matches a pattern no one has seen
+                let buyer = (batch_index * LANES + lane + 1) as u32;
+                let prices = secrets.iter().map(|step_secrets| (self.price)(step_secrets[lane]));
+                for (pattern, price) in self.patterns(prices) {
+                    totals.record(pattern, buyer, price);
+                }
+            }
+        }
+        (secret_sum, totals)
+    }
+}
+
+/// The total bananas bought with each pattern, counting only the first sale for each buyer.
+pub enum Totals {
+    /// Indexed by pattern, with the last buyer to see each pattern, so the array of buyers never
+    /// needs clearing
+    Flat { totals: Vec<u64>, seen: Vec<u32> },
+    /// For patterns too long to index in an array, keyed by pattern
+    Sparse(HashMap<u64, (u32, u64)>),
+}
+
+impl Totals {
+    fn new(patterns: u64) -> Totals {
+        if patterns <= FLAT_PATTERNS {
+            Totals::Flat { totals: vec![0; patterns as usize], seen: vec![0; patterns as usize] }
+        } else {
+            Totals::Sparse(HashMap::new())
+        }
+    }
+
+    fn record(&mut self, pattern: u64, buyer: u32, price: u32) {
+        match self {
+            Totals::Flat { totals, seen } => {
+                let pattern = pattern as usize;
+                if seen[pattern] != buyer {
+                    seen[pattern] = buyer;
+                    totals[pattern] += price as u64;
+                }
+            }
+            Totals::Sparse(totals) => {
+                let (seen, total) = totals.entry(pattern).or_default();
+                if *seen != buyer {
+                    *seen = buyer;
+                    *total += price as u64;
+                }
+            }
+        }
+    }
+
+    fn merge(mut self, other: Totals) -> Totals {
+        match (&mut self, other) {
+            (Totals::Flat { totals, .. }, Totals::Flat { totals: other, .. }) => {
+                totals.iter_mut().zip(other).for_each(|(total, n)| *total += n);
+            }
+            (Totals::Sparse(totals), Totals::Sparse(other)) => {
+                for (pattern, (_, n)) in other {
+                    totals.entry(pattern).or_default().1 += n;
+                }
+            }
+            _ => panic!("Cannot merge totals of different sizes"),
+        }
+        self
+    }
+
+    /// The most bananas that can be bought with a single pattern of price changes.
+    pub fn best(&self) -> u64 {
+        self.iter().map(|(_, total)| total).max().unwrap_or(0)
+    }
+
+    /// Every pattern that buys any bananas, with its total.
+    pub fn iter(&self) -> Box<dyn Iterator<Item = (u64, u64)> + '_> {
+        match self {
+            Totals::Flat { totals, .. } => Box::new(totals.iter().enumerate()
+                .filter(|&(_, &total)| total > 0)
+                .map(|(pattern, &total)| (pattern as u64, total))),
+            Totals::Sparse(totals) => Box::new(totals.iter()
+                .filter(|&(_, &(_, total))| total > 0)
+                .map(|(&pattern, &(_, total))| (pattern, total))),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// The example's buyers, followed by more whose seeds are generated from the example's.
+    fn seeds() -> Vec<u32> {
+        let generated = std::iter::successors(Some(2024), |&n| Some(next_secret(n ^ 0x5a5a5a))).skip(1).take(200);
+        [1, 2, 3, 2024].into_iter().chain(generated).collect()
+    }
+
+    /// The original implementation, hashing the price changes for each buyer.
+    fn reference_best_total(seeds: &[u32], market: &Market) -> u64 {
+        let mut totals: HashMap<Vec<i64>, u64> = HashMap::new();
+        for &seed in seeds {
+            let prices: Vec<i64> = market.buyer_prices(seed).map(|p| p as i64).collect();
+            let first_prices: HashMap<Vec<i64>, u64> = prices.windows(market.window + 1)
+                .map(|p| (p.windows(2).map(|w| w[1] - w[0]).collect(), p[market.window] as u64))
+                .rev()
+                .collect();
+            first_prices.into_iter().for_each(|(pattern, price)| *totals.entry(pattern).or_default() += price);
+        }
+        totals.into_values().max().unwrap_or(0)
+    }
+
+    #[test]
+    fn finds_best_pattern_for_example() {
+        let seeds = [1, 2, 3, 2024];
+        assert_eq!(Market::standard().analyse(&seeds).1.best(), 23);
+        assert_eq!(Market::standard().analyse(&[1, 10, 100, 2024]).0, 37327623);
+        let best = &Market::standard().top_patterns(&seeds, 3)[0];
+        assert_eq!(best.changes, vec![-2, 1, -1, 3]);
+        assert_eq!(best.sales, vec![Some(7), Some(7), None, Some(9)]);
+    }
+
+    #[test]
+    fn matches_reference_implementation() {
+        let market = Market::standard();
+        assert_eq!(reference_best_total(&[1, 2, 3, 2024], &market), 23);
+        let seeds = seeds();
+        assert_eq!(market.analyse(&seeds).1.best(), reference_best_total(&seeds, &market));
+    }
+
+    #[test]
+    fn handles_the_longest_indexable_window() {
+        // 19^15 patterns fit in a u64, but shifting a full pattern by another digit would not
+        let market = Market { window: 15, steps: 50, prices: 10, price: &last_digit };
+        let seeds = &seeds()[..20];
+        assert_eq!(market.analyse(seeds).1.best(), reference_best_total(seeds, &market));
+        let best = &market.top_patterns(seeds, 1)[0];
+        let (seed, price) = seeds.iter().zip(&best.sales).find_map(|(seed, price)| Some((seed, (*price)?))).unwrap();
+        let prices: Vec<i32> = market.buyer_prices(*seed).map(|p| p as i32).collect();
+        let changes: Vec<i32> = prices.windows(2).map(|w| w[1] - w[0]).collect();
+        let end = changes.windows(15).position(|window| window == best.changes).unwrap() + 15;
+        assert_eq!(prices[end] as u32, price);
+    }
+
+    #[test]
+    fn supports_other_windows_and_prices() {
+        let seeds = &seeds()[..100];
+        let modulo_7 = |secret| secret % 7;
+        for window in [1, 3, 6] {
+            let market = Market { window, steps: 500, prices: 7, price: &modulo_7 };
+            assert_eq!(market.analyse(seeds).1.best(), reference_best_total(seeds, &market));
+            let top = market.top_patterns(seeds, 5);
+            assert!(top.windows(2).all(|pair| pair[0].total >= pair[1].total));
+            for report in top {
+                assert_eq!(report.changes.len(), window);
+                assert_eq!(report.sales.iter().flatten().map(|&price| price as u64).sum::<u64>(), report.total);
+            }
+        }
+    }
+}
@@ -0,0 +1,180 @@
+use std::collections::HashSet;
+
+use crate::graph::Graph;
+
+/// Text formats a network can be read from and written to.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Format {
+    /// One `a-b` connection per line, as in the puzzle input
+    EdgeList,
+    /// One `a: b c ...` line per node, listing its neighbours
+    Adjacency,
+    /// An undirected Graphviz graph
+    Dot,
+}
+
+impl Format {
+    pub fn parse(name: &str) -> Option<Format> {
+        match name {
+            "edges" => Some(Format::EdgeList),
+            "adjacency" => Some(Format::Adjacency),
+            "dot" => Some(Format::Dot),
+            _ => None,
+        }
+    }
+
+    /// Guesses the format of a file from its extension, defaulting to an edge list.
+    pub fn from_filename(filename: &str) -> Format {
+        match filename.rsplit_once('.').map(|(_, extension)| extension) {
+            Some("dot" | "gv") => Format::Dot,
+            Some("adj") => Format::Adjacency,
+            _ => Format::EdgeList,
+        }
+    }
+}
+
+pub fn read(input: &str, format: Format) -> Result<Graph, String> {
+    let mut graph = Graph::new();
+    match format {
+        Format::EdgeList => {
+            for line in input.lines().map(str::trim).filter(|line| !line.is_empty()) {
+                let (a, b) = line.split_once('-').ok_or_else(|| format!("Invalid connection '{line}'"))?;
+                add_edge(&mut graph, a.trim(), b.trim())?;
+            }
+        }
+        Format::Adjacency => {
+            for line in input.lines().map(str::trim).filter(|line| !line.is_empty()) {
+                let (node, neighbours) = line.split_once(':').ok_or_else(|| format!("Invalid adjacency list '{line}'"))?;
+                graph.intern(node.trim());
+                for neighbour in neighbours.split_whitespace() {
+                    add_edge(&mut graph, node.trim(), neighbour)?;
+                }
+            }
+        }
+        Format::Dot => read_dot(&mut graph, input)?,
+    }
+    Ok(graph)
+}
+
+fn add_edge(graph: &mut Graph, a: &str, b: &str) -> Result<(), String> {
+    if a.is_empty() || b.is_empty() || a == b {
+        return Err(format!("Invalid connection '{a}-{b}'"));
+    }
+    let (a, b) = (graph.intern(a), graph.intern(b));
+    graph.add_edge(a, b);
+    Ok(())
+}
+
+/// Reads the subset of DOT that describes an undirected graph: node statements and chains of
+/// `--` edges, separated by semicolons or newlines. Attributes and graph settings are ignored.
+fn read_dot(graph: &mut Graph, input: &str) -> Result<(), String> {
+    let body = input.trim()
+        .strip_suffix('}')
+        .and_then(|rest| rest.split_once('{'))
+        .filter(|(header, _)| header.split_whitespace().any(|word| word == "graph"))
+        .map(|(_, body)| body)
+        .ok_or("Expected an undirected graph { ... }")?;
+    for statement in body.split([';', '\n']).map(strip_attributes).map(str::trim) {
+        if statement.is_empty() || statement.contains('=') || ["graph", "node", "edge"].contains(&statement) {
+            continue;
+        }
+        if statement.contains("->") {
+            return Err(format!("Directed edge in '{statement}'"));
+        }
+        let nodes: Vec<&str> = statement.split("--").map(|node| node.trim().trim_matches('"')).collect();
+        if let [node] = nodes[..] {
+            graph.intern(node);
+        }
+        for pair in nodes.windows(2) {
+            add_edge(graph, pair[0], pair[1])?;
+        }
+    }
+    Ok(())
+}
+
+fn strip_attributes(statement: &str) -> &str {
+    statement.split_once('[').map_or(statement, |(before, _)| before)
+}
+
+/// Writes the graph with nodes in id order. In DOT, the `highlight` nodes and the edges between
+/// them are drawn in red; other formats ignore it.
+pub fn write(graph: &Graph, format: Format, highlight: &[usize]) -> String {
+    let mut output = String::new();
+    match format {
+        Format::EdgeList => {
+            for (a, b) in edges(graph) {
+                output.push_str(&format!("{}-{}\n", graph.name(a), graph.name(b)));
+            }
+        }
+        Format::Adjacency => {
+            for node in 0..graph.len() {
+                output.push_str(graph.name(node));
+                output.push(':');
+                for &neighbour in graph.neighbours(node) {
+                    output.push(' ');
+                    output.push_str(graph.name(neighbour));
+                }
+                output.push('\n');
+            }
+        }
+        Format::Dot => {
+            let highlight: HashSet<usize> = highlight.iter().copied().collect();
+            output.push_str("graph {\n");
+            for node in 0..graph.len() {
+                let style = if highlight.contains(&node) { " [style=filled, fillcolor=red]" } else { "" };
+                output.push_str(&format!("  \"{}\"{style};\n", graph.name(node)));
+            }
+            for (a, b) in edges(graph) {
+                let style = if highlight.contains(&a) && highlight.contains(&b) { " [color=red, penwidth=2]" } else { "" };
+                output.push_str(&format!("  \"{}\" -- \"{}\"{style};\n", graph.name(a), graph.name(b)));
+            }
+            output.push_str("}\n");
+        }
+    }
+    output
+}
+
+/// Each edge once, as `(a, b)` with `a < b`.
+fn edges(graph: &Graph) -> impl Iterator<Item = (usize, usize)> + '_ {
+    (0..graph.len()).flat_map(move |a| graph.neighbours(a).iter().filter(move |&&b| b > a).map(move |&b| (a, b)))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    fn edge_names(graph: &Graph) -> HashSet<(String, String)> {
+        edges(graph)
+            .map(|(a, b)| {
+                let (a, b) = (graph.name(a).to_string(), graph.name(b).to_string());
+                if a < b { (a, b) } else { (b, a) }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn round_trips_every_format() {
+        let graph = read(&fs::read_to_string("test_input.txt").unwrap(), Format::EdgeList).unwrap();
+        for format in [Format::EdgeList, Format::Adjacency, Format::Dot] {
+            let written = write(&graph, format, &graph.maximum_clique());
+            let copy = read(&written, format).unwrap();
+            assert_eq!(copy.len(), graph.len());
+            assert_eq!(edge_names(&copy), edge_names(&graph));
+        }
+    }
+
+    #[test]
+    fn reads_dot_and_adjacency_lists() {
+        let graph = read("graph lan {\n  node [shape=box]\n  a -- b -- \"c\" [color=red]; d\n  b -- d;\n}", Format::Dot).unwrap();
+        assert_eq!(graph.len(), 4);
+        assert_eq!(graph.edge_count(), 3);
+        assert!(read("digraph { a -> b }", Format::Dot).is_err());
+
+        let graph = read("a: b c\nb: c\nd:\n", Format::Adjacency).unwrap();
+        assert_eq!((graph.len(), graph.edge_count()), (4, 3));
+        assert_eq!(write(&graph, Format::Adjacency, &[]), "a: b c\nb: a c\nc: a b\nd:\n");
+        assert!(read("a-a", Format::EdgeList).is_err());
+    }
+}
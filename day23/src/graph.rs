@@ -0,0 +1,301 @@
+use std::collections::{BTreeMap, HashMap};
+
+use crate::formats::{read, Format};
+
+/// An undirected graph whose nodes are interned names, numbered in the order they were first seen.
+/// Each node's neighbours are kept sorted, so neighbourhoods can be intersected by merging.
+#[derive(Default)]
+pub struct Graph {
+    names: Vec<String>,
+    ids: HashMap<String, usize>,
+    adjacency: Vec<Vec<usize>>,
+}
+
+impl Graph {
+    pub fn new() -> Graph {
+        Graph::default()
+    }
+
+    /// Parses one `a-b` connection per line.
+    pub fn parse(input: &str) -> Graph {
+        read(input, Format::EdgeList).unwrap_or_else(|e| panic!("{e}"))
+    }
+
+    /// Returns the id of the node with this name, adding it if it is new.
+    pub fn intern(&mut self, name: &str) -> usize {
+        if let Some(&id) = self.ids.get(name) {
+            return id;
+        }
+        let id = self.names.len();
+        self.names.push(name.to_string());
+        self.ids.insert(name.to_string(), id);
+        self.adjacency.push(Vec::new());
+        id
+    }
+
+    pub fn add_edge(&mut self, a: usize, b: usize) {
+        assert_ne!(a, b, "Node '{}' cannot be connected to itself", self.names[a]);
+        for (from, to) in [(a, b), (b, a)] {
+            if let Err(index) = self.adjacency[from].binary_search(&to) {
+                self.adjacency[from].insert(index, to);
+            }
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.names.len()
+    }
+
+    pub fn edge_count(&self) -> usize {
+        self.adjacency.iter().map(Vec::len).sum::<usize>() / 2
+    }
+
+    pub fn name(&self, id: usize) -> &str {
+        &self.names[id]
+    }
+
+    pub fn neighbours(&self, id: usize) -> &[usize] {
+        &self.adjacency[id]
+    }
+
+    /// Every triangle `[a, b, c]` with `a < b < c`. Each edge `a-b` is extended by the common
+    /// neighbours of `a` and `b` greater than `b`, so each triangle is found exactly once.
+    pub fn triangles(&self) -> Vec<[usize; 3]> {
+        let mut triangles = Vec::new();
+        for a in 0..self.len() {
+            for &b in self.neighbours(a).iter().filter(|&&b| b > a) {
+                let common = intersect(self.neighbours(a), self.neighbours(b));
+                triangles.extend(common.into_iter().filter(|&c| c > b).map(|c| [a, b, c]));
+            }
+        }
+        triangles
+    }
+
+    /// Orders the nodes by repeatedly removing one of smallest remaining degree. Each node then has
+    /// at most the graph's degeneracy of neighbours later in the order.
+    pub fn degeneracy_order(&self) -> Vec<usize> {
+        self.peel().0
+    }
+
+    /// The core number of each node: the largest `k` such that the node is in the `k`-core, the
+    /// largest subgraph in which every node has at least `k` neighbours.
+    pub fn core_numbers(&self) -> Vec<usize> {
+        self.peel().1
+    }
+
+    /// Removes nodes in degeneracy order, returning that order and each node's core number, which
+    /// is the highest degree seen at the time of any removal up to and including its own.
+    fn peel(&self) -> (Vec<usize>, Vec<usize>) {
+        let mut degrees: Vec<usize> = self.adjacency.iter().map(Vec::len).collect();
+        let max_degree = degrees.iter().copied().max().unwrap_or(0);
+        let mut buckets: Vec<Vec<usize>> = vec![Vec::new(); max_degree + 1];
+        for (node, &degree) in degrees.iter().enumerate() {
+            buckets[degree].push(node);
+        }
+        let mut removed = vec![false; self.len()];
+        let mut order = Vec::with_capacity(self.len());
+        let mut cores = vec![0; self.len()];
+        let (mut lowest, mut core) = (0, 0);
+        while order.len() < self.len() {
+            // Buckets may hold stale entries for nodes whose degree has since dropped
+            let Some(node) = buckets[lowest].pop() else {
+                lowest += 1;
+                continue;
+            };
+            if removed[node] || degrees[node] != lowest {
+                continue;
+            }
+            removed[node] = true;
+            order.push(node);
+            core = core.max(lowest);
+            cores[node] = core;
+            for &neighbour in self.neighbours(node) {
+                if !removed[neighbour] {
+                    degrees[neighbour] -= 1;
+                    buckets[degrees[neighbour]].push(neighbour);
+                    lowest = lowest.min(degrees[neighbour]);
+                }
+            }
+        }
+        (order, cores)
+    }
+
+    /// The number of nodes with each degree.
+    pub fn degree_distribution(&self) -> BTreeMap<usize, usize> {
+        let mut distribution = BTreeMap::new();
+        for neighbours in &self.adjacency {
+            *distribution.entry(neighbours.len()).or_insert(0) += 1;
+        }
+        distribution
+    }
+
+    /// The connected components, each sorted by id, in order of their smallest node.
+    pub fn components(&self) -> Vec<Vec<usize>> {
+        let mut visited = vec![false; self.len()];
+        let mut components = Vec::new();
+        for start in 0..self.len() {
+            if visited[start] {
+                continue;
+            }
+            visited[start] = true;
+            let mut component = vec![start];
+            let mut stack = vec![start];
+            while let Some(node) = stack.pop() {
+                for &neighbour in self.neighbours(node) {
+                    if !visited[neighbour] {
+                        visited[neighbour] = true;
+                        component.push(neighbour);
+                        stack.push(neighbour);
+                    }
+                }
+            }
+            component.sort_unstable();
+            components.push(component);
+        }
+        components
+    }
+
+    /// Enumerates every maximal clique, each sorted by id, using Bron–Kerbosch with pivoting. The
+    /// outer level visits nodes in degeneracy order, so each branch starts with few candidates.
+    pub fn maximal_cliques(&self) -> Vec<Vec<usize>> {
+        let order = self.degeneracy_order();
+        let mut position = vec![0; self.len()];
+        for (i, &node) in order.iter().enumerate() {
+            position[node] = i;
+        }
+        let mut cliques = Vec::new();
+        for &node in &order {
+            let (later, earlier) = self.neighbours(node).iter().partition(|&&n| position[n] > position[node]);
+            self.bron_kerbosch(&mut vec![node], later, earlier, &mut cliques);
+        }
+        cliques
+    }
+
+    /// A largest clique, sorted by id.
+    pub fn maximum_clique(&self) -> Vec<usize> {
+        self.maximal_cliques().into_iter().max_by_key(Vec::len).unwrap_or_default()
+    }
+
+    /// The size of the largest clique.
+    pub fn clique_number(&self) -> usize {
+        self.maximum_clique().len()
+    }
+
+    /// Extends `clique` with `candidates`, excluding cliques that would also contain a node of
+    /// `excluded`, as those are found from another branch. `candidates` and `excluded` are sorted.
+    fn bron_kerbosch(&self, clique: &mut Vec<usize>, candidates: Vec<usize>, mut excluded: Vec<usize>, cliques: &mut Vec<Vec<usize>>) {
+        if candidates.is_empty() {
+            if excluded.is_empty() {
+                let mut found = clique.clone();
+                found.sort_unstable();
+                cliques.push(found);
+            }
+            return;
+        }
+        // Any maximal clique contains the pivot or a non-neighbour of it, so only those are branched on
+        let pivot = candidates.iter().chain(&excluded)
+            .copied()
+            .max_by_key(|&u| intersect(&candidates, self.neighbours(u)).len())
+            .unwrap();
+        let mut remaining = candidates.clone();
+        for node in candidates {
+            if self.neighbours(pivot).binary_search(&node).is_ok() {
+                continue;
+            }
+            clique.push(node);
+            let neighbours = self.neighbours(node);
+            self.bron_kerbosch(clique, intersect(&remaining, neighbours), intersect(&excluded, neighbours), cliques);
+            clique.pop();
+            remaining.retain(|&n| n != node);
+            if let Err(index) = excluded.binary_search(&node) {
+                excluded.insert(index, node);
+            }
+        }
+    }
+}
+
+/// The common elements of two sorted slices.
+fn intersect(a: &[usize], b: &[usize]) -> Vec<usize> {
+    let mut common = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < a.len() && j < b.len() {
+        match a[i].cmp(&b[j]) {
+            std::cmp::Ordering::Less => i += 1,
+            std::cmp::Ordering::Greater => j += 1,
+            std::cmp::Ordering::Equal => {
+                common.push(a[i]);
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    common
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use std::fs;
+
+    use super::*;
+
+    fn names(graph: &Graph, clique: &[usize]) -> Vec<String> {
+        let mut names: Vec<String> = clique.iter().map(|&id| graph.name(id).to_string()).collect();
+        names.sort();
+        names
+    }
+
+    #[test]
+    fn finds_example_triangles_and_clique() {
+        let graph = Graph::parse(&fs::read_to_string("test_input.txt").unwrap());
+        let triangles = graph.triangles();
+        assert_eq!(triangles.len(), 12);
+        assert_eq!(triangles.iter().filter(|t| t.iter().any(|&n| graph.name(n).starts_with('t'))).count(), 7);
+        assert_eq!(names(&graph, &graph.maximum_clique()).join(","), "co,de,ka,ta");
+    }
+
+    #[test]
+    fn enumerates_every_maximal_clique() {
+        // Two triangles sharing an edge, a pendant edge and an isolated pair
+        let graph = Graph::parse("a-b\nb-c\na-c\nb-d\nc-d\nd-e\nf-g");
+        let cliques: HashSet<Vec<String>> = graph.maximal_cliques().iter().map(|c| names(&graph, c)).collect();
+        let expected: HashSet<Vec<String>> = [vec!["a", "b", "c"], vec!["b", "c", "d"], vec!["d", "e"], vec!["f", "g"]]
+            .into_iter()
+            .map(|c| c.into_iter().map(String::from).collect())
+            .collect();
+        assert_eq!(cliques, expected);
+    }
+
+    #[test]
+    fn reports_statistics() {
+        // A 4-clique with a tail of two, plus a separate pair
+        let graph = Graph::parse("a-b\na-c\na-d\nb-c\nb-d\nc-d\nd-e\ne-f\ng-h");
+        assert_eq!(graph.edge_count(), 9);
+        assert_eq!(graph.degree_distribution(), BTreeMap::from([(1, 3), (2, 1), (3, 3), (4, 1)]));
+        let components: Vec<Vec<String>> = graph.components().iter().map(|c| names(&graph, c)).collect();
+        assert_eq!(components, vec![vec!["a", "b", "c", "d", "e", "f"], vec!["g", "h"]]);
+        assert_eq!(graph.clique_number(), 4);
+        assert_eq!(graph.core_numbers(), vec![3, 3, 3, 3, 1, 1, 1, 1]);
+    }
+
+    /// The largest number of neighbours any node has later in `order`.
+    fn later_neighbours(graph: &Graph, order: &[usize]) -> usize {
+        assert_eq!(order.iter().copied().collect::<HashSet<_>>().len(), graph.len());
+        let mut position = vec![0; graph.len()];
+        order.iter().enumerate().for_each(|(i, &node)| position[node] = i);
+        let later = |node: usize| graph.neighbours(node).iter().filter(|&&n| position[n] > position[node]).count();
+        order.iter().map(|&node| later(node)).max().unwrap()
+    }
+
+    #[test]
+    fn orders_by_degeneracy() {
+        let graph = Graph::parse(&fs::read_to_string("test_input.txt").unwrap());
+        let degeneracy = later_neighbours(&graph, &graph.degeneracy_order());
+        assert_eq!(degeneracy, *graph.core_numbers().iter().max().unwrap());
+        assert!(graph.maximum_clique().len() <= degeneracy + 1);
+
+        // A 4-clique with a tail, whose nodes each have at most three later neighbours
+        let graph = Graph::parse("a-b\na-c\na-d\nb-c\nb-d\nc-d\nd-e\ne-f");
+        assert_eq!(later_neighbours(&graph, &graph.degeneracy_order()), 3);
+    }
+}
@@ -1,58 +1,68 @@
-use std::collections::{HashMap, HashSet};
+use std::env;
+use std::fs;
 
 use itertools::Itertools;
 
-const DAY: u8 = 23;
+use formats::{read, write, Format};
+use graph::Graph;
 
-fn main() {
-    let input = aocutil::load_input(DAY);
+mod formats;
+mod graph;
 
-    let connections = parse_input(&input);
-    let computers: Vec<&str> = connections.keys().copied().sorted().collect();
+const DAY: u8 = 23;
 
-    println!("Part 1: {}", count_triple_parties(&computers, &connections));
-    println!("Part 2: {}", find_biggest_party(Vec::new(), &connections, &computers).iter().sorted().join(","));
-}
+/// Usage: `day23 [stats | export FORMAT] [FILE]`
+///
+/// With `stats`, reports statistics about the network. With `export`, writes it as `edges`,
+/// `adjacency` or `dot`, where DOT output highlights the largest party. `FILE` is read in the
+/// format given by its extension: `.dot`, `.adj` or otherwise an edge list.
+fn main() {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let (command, rest) = match args.first().map(String::as_str) {
+        Some("stats") => ("stats", &args[1..]),
+        Some("export") => ("export", &args[1..]),
+        _ => ("", &args[..]),
+    };
+    let (export_format, rest) = match command {
+        "export" => {
+            let name = rest.first().expect("Usage: day23 export FORMAT [FILE]");
+            (Some(Format::parse(name).unwrap_or_else(|| panic!("Unknown format '{name}'"))), &rest[1..])
+        }
+        _ => (None, rest),
+    };
+    let graph = match rest.first() {
+        Some(filename) => {
+            let input = fs::read_to_string(filename).unwrap_or_else(|e| panic!("Unable to read file {}: {}", filename, e));
+            read(&input, Format::from_filename(filename)).unwrap_or_else(|e| panic!("Invalid network in {filename}: {e}"))
+        }
+        None => Graph::parse(&aocutil::load_input(DAY)),
+    };
 
-fn count_triple_parties(computers: &[&str], connections: &HashMap<&str, HashSet<&str>>) -> i64 {
-    let mut total = 0;
-    for (i, &n1) in computers[0..].iter().enumerate() {
-        for (j, &n2) in computers[i + 1..].iter().enumerate() {
-            if can_add_to_party(&[n1], n2, connections) {
-                for &n3 in computers[i + j + 1..].iter() {
-                    if can_add_to_party(&[n1, n2], n3, connections) && (n1.starts_with('t') || n2.starts_with('t') || n3.starts_with('t')) {
-                        total += 1;
-                    }
-                }
-            }
+    match (command, export_format) {
+        ("stats", _) => print_stats(&graph),
+        (_, Some(format)) => print!("{}", write(&graph, format, &graph.maximum_clique())),
+        _ => {
+            println!("Part 1: {}", count_triple_parties(&graph));
+            println!("Part 2: {}", graph.maximum_clique().iter().map(|&id| graph.name(id)).sorted().join(","));
         }
     }
-    total
 }
 
-fn find_biggest_party<'a>(party: Vec<&'a str>, connections: &HashMap<&str, HashSet<&str>>, remaining_computers: &[&'a str]) -> Vec<&'a str> {
-    remaining_computers.iter().enumerate()
-        .filter(|&(_i, candidate)| can_add_to_party(&party, candidate, connections))
-        .map(|(i, candidate)| {
-            let new_party = party.iter().chain([candidate]).cloned().collect();
-            find_biggest_party(new_party, connections, &remaining_computers[i + 1..])
-        })
-        .max_by_key(|p| p.len())
-        .unwrap_or(party)
+fn count_triple_parties(graph: &Graph) -> usize {
+    graph.triangles().iter()
+        .filter(|triangle| triangle.iter().any(|&id| graph.name(id).starts_with('t')))
+        .count()
 }
 
-fn can_add_to_party(party: &[&str], candidate: &str, connections: &HashMap<&str, HashSet<&str>>) -> bool {
-    let candidate_neighbours = &connections[candidate];
-    party.iter().all(|party_member| candidate_neighbours.contains(party_member))
+fn print_stats(graph: &Graph) {
+    println!("Computers: {}, connections: {}", graph.len(), graph.edge_count());
+    println!("Degrees: {}", graph.degree_distribution().iter().map(|(degree, count)| format!("{degree}: {count}")).join(", "));
+    let components = graph.components();
+    println!("Components: {}, sizes: {}", components.len(), components.iter().map(Vec::len).sorted().rev().join(", "));
+    println!("Triangles: {}", graph.triangles().len());
+    println!("Clique number: {}", graph.clique_number());
+    let cores = graph.core_numbers();
+    for k in cores.iter().copied().unique().sorted() {
+        println!("{k}-core: {} computers", cores.iter().filter(|&&core| core >= k).count());
+    }
 }
-
-fn parse_input(input: &str) -> HashMap<&str, HashSet<&str>> {
-    let mut connections = HashMap::new();
-    input.lines()
-        .map(|line| line.split_once("-").unwrap())
-        .for_each(|(n1, n2)| {
-            connections.entry(n1).or_insert(HashSet::new()).insert(n2);
-            connections.entry(n2).or_insert(HashSet::new()).insert(n1);
-        });
-    connections
-}
\ No newline at end of file
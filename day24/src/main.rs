@@ -112,11 +112,10 @@ fn generate_diagram(gates: &Gates) -> std::io::Result<String> {
                 None
             };
             if op == "OR" {
-                if and1_bit.is_none() || and2_bit.is_none() {
-                    labels.insert(gate, GateInfo::Error("Gate should have an input from both AND1 and AND2".to_string()));
-                } else {
-                    labels.insert(gate, GateInfo::OR(and2_bit.unwrap()));
-                }
+                match (and1_bit, and2_bit) {
+                    (Some(_), Some(bit)) => labels.insert(gate, GateInfo::OR(bit)),
+                    _ => labels.insert(gate, GateInfo::Error("Gate should have an input from both AND1 and AND2".to_string())),
+                };
             } else {
                 labels.insert(gate, GateInfo::Error("Unrecognised gate".to_string()));
             }
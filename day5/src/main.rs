@@ -1,23 +1,48 @@
-use std::cmp::Ordering;
-use std::collections::HashSet;
-
-use itertools::Itertools;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::env;
 
 const DAY: u8 = 5;
 
+type Rules = HashSet<(i64, i64)>;
+
+/// Pages of an update in an order consistent with the rules.
+#[derive(Debug, PartialEq)]
+struct SortedUpdate {
+    pages: Vec<i64>,
+    /// The first pair of pages that the rules do not order relative to each other, if any. When
+    /// the order is ambiguous the lowest numbered page is placed first.
+    ambiguous: Option<(i64, i64)>,
+}
+
+/// Usage: `day5 [explain]`
+///
+/// With `explain`, prints the rules broken by each update that is not in the correct order.
 fn main() {
+    let explain = env::args().nth(1).is_some_and(|arg| arg == "explain");
     let input = aocutil::load_input(DAY);
     let (rules, updates) = parse_input(&input);
 
     let mut part1 = 0;
     let mut part2 = 0;
     for update in updates {
-        let sorted_update = sort_update(&update, &rules);
-        let mid = sorted_update[sorted_update.len() / 2];
-        if sorted_update == update {
+        let sorted_update = match sort_update(&update, &rules) {
+            Ok(sorted_update) => sorted_update,
+            Err(cycle) => {
+                println!("Skipping update {update:?}: rules contain a cycle {cycle:?}");
+                continue;
+            }
+        };
+        if let Some((a, b)) = sorted_update.ambiguous {
+            println!("Warning: rules do not order pages {a} and {b} in update {update:?}");
+        }
+        let mid = sorted_update.pages[sorted_update.pages.len() / 2];
+        if sorted_update.pages == update {
             part1 += mid;
         } else {
             part2 += mid;
+            if explain {
+                println!("Update {update:?} breaks rules {:?}", broken_rules(&update, &rules));
+            }
         }
     }
 
@@ -25,23 +50,94 @@ fn main() {
     println!("Part 2: {part2}");
 }
 
-fn sort_update(update: &[i64], rules: &HashSet<(i64, i64)>) -> Vec<i64> {
-    update.iter()
-        .sorted_by(|&a, &b|
-            if rules.contains(&(*a, *b)) {
-                Ordering::Less
-            } else {
-                Ordering::Greater
+/// Topologically sorts the pages of an update using Kahn's algorithm over the rules that apply to
+/// those pages. Returns the pages that form a cycle if the rules cannot be satisfied.
+fn sort_update(update: &[i64], rules: &Rules) -> Result<SortedUpdate, Vec<i64>> {
+    let pages: HashSet<i64> = update.iter().copied().collect();
+    let mut successors: HashMap<i64, Vec<i64>> = HashMap::new();
+    let mut in_degree: HashMap<i64, usize> = pages.iter().map(|&page| (page, 0)).collect();
+    for &(before, after) in rules {
+        if pages.contains(&before) && pages.contains(&after) {
+            successors.entry(before).or_default().push(after);
+            *in_degree.get_mut(&after).unwrap() += 1;
+        }
+    }
+
+    let mut ready: BTreeSet<i64> = in_degree.iter()
+        .filter(|(_, &degree)| degree == 0)
+        .map(|(&page, _)| page)
+        .collect();
+    let mut sorted = vec![];
+    let mut ambiguous = None;
+    while let Some(page) = ready.pop_first() {
+        if ambiguous.is_none() {
+            if let Some(&other) = ready.first() {
+                ambiguous = Some((page, other));
             }
-        )
-        .copied()
-        .collect()
+        }
+        sorted.push(page);
+        for after in successors.get(&page).into_iter().flatten() {
+            let degree = in_degree.get_mut(after).unwrap();
+            *degree -= 1;
+            if *degree == 0 {
+                ready.insert(*after);
+            }
+        }
+    }
+
+    if sorted.len() < pages.len() {
+        return Err(find_cycle(&in_degree, rules));
+    }
+
+    // Keep any repeated pages in the update, placed alongside their first occurrence
+    let mut counts: HashMap<i64, usize> = HashMap::new();
+    update.iter().for_each(|&page| *counts.entry(page).or_default() += 1);
+    let pages = sorted.iter().flat_map(|page| std::iter::repeat_n(*page, counts[page])).collect();
+
+    Ok(SortedUpdate { pages, ambiguous })
 }
 
-fn parse_input(input: &str) -> (HashSet<(i64, i64)>, Vec<Vec<i64>>) {
+/// Walks backwards from a page that Kahn's algorithm could not place until a page repeats. Every
+/// such page has a predecessor that also could not be placed, so the walk always finds a cycle.
+fn find_cycle(in_degree: &HashMap<i64, usize>, rules: &Rules) -> Vec<i64> {
+    let remaining: BTreeSet<i64> = in_degree.iter()
+        .filter(|(_, &degree)| degree > 0)
+        .map(|(&page, _)| page)
+        .collect();
+    let mut path = vec![*remaining.first().unwrap()];
+    loop {
+        let page = *path.last().unwrap();
+        let predecessor = *remaining.iter()
+            .find(|&&before| rules.contains(&(before, page)))
+            .unwrap();
+        if let Some(start) = path.iter().position(|&p| p == predecessor) {
+            let mut cycle = path[start..].to_vec();
+            cycle.reverse();
+            let lowest = cycle.iter().position(|&p| p == *cycle.iter().min().unwrap()).unwrap();
+            cycle.rotate_left(lowest);
+            return cycle;
+        }
+        path.push(predecessor);
+    }
+}
+
+/// Returns the rules broken by an update, in the order the offending pages appear.
+fn broken_rules(update: &[i64], rules: &Rules) -> Vec<(i64, i64)> {
+    let mut broken = vec![];
+    for (i, &after) in update.iter().enumerate() {
+        for &before in &update[i + 1..] {
+            if rules.contains(&(before, after)) {
+                broken.push((before, after));
+            }
+        }
+    }
+    broken
+}
+
+fn parse_input(input: &str) -> (Rules, Vec<Vec<i64>>) {
     let (block1, block2) = input.split_once("\n\n").unwrap();
 
-    let rules: HashSet<(i64, i64)> = block1.lines()
+    let rules: Rules = block1.lines()
         .map(|line| {
             let (before, after) = line.split_once('|').unwrap();
             (before.parse().unwrap(), after.parse().unwrap())
@@ -53,4 +149,46 @@ fn parse_input(input: &str) -> (HashSet<(i64, i64)>, Vec<Vec<i64>>) {
         .collect();
 
     (rules, updates)
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    #[test]
+    fn sorts_example_updates() {
+        let (rules, updates) = parse_input(&fs::read_to_string("test_input.txt").unwrap());
+        let sorted: Vec<Vec<i64>> = updates.iter()
+            .map(|update| sort_update(update, &rules).unwrap())
+            .inspect(|sorted_update| assert_eq!(sorted_update.ambiguous, None))
+            .map(|sorted_update| sorted_update.pages)
+            .collect();
+        assert_eq!(sorted[3], vec![97, 75, 47, 61, 53]);
+        assert_eq!(sorted[4], vec![61, 29, 13]);
+        assert_eq!(sorted[5], vec![97, 75, 47, 29, 13]);
+    }
+
+    #[test]
+    fn explains_broken_rules() {
+        let (rules, _) = parse_input(&fs::read_to_string("test_input.txt").unwrap());
+        assert_eq!(broken_rules(&[75, 97, 47, 61, 53], &rules), vec![(97, 75)]);
+        assert_eq!(broken_rules(&[61, 13, 29], &rules), vec![(29, 13)]);
+        assert_eq!(broken_rules(&[75, 47, 61, 53, 29], &rules), vec![]);
+    }
+
+    #[test]
+    fn reports_ambiguous_order() {
+        let rules = Rules::from([(3, 1)]);
+        assert_eq!(sort_update(&[1, 2, 3], &rules), Ok(SortedUpdate { pages: vec![2, 3, 1], ambiguous: Some((2, 3)) }));
+    }
+
+    #[test]
+    fn reports_cycle() {
+        let rules = Rules::from([(1, 2), (2, 3), (3, 1), (4, 1)]);
+        assert_eq!(sort_update(&[4, 3, 2, 1], &rules), Err(vec![1, 2, 3]));
+        // The cycle is irrelevant when one of its pages is missing from the update
+        assert!(sort_update(&[4, 2, 1], &rules).is_ok());
+    }
+}
@@ -1,38 +1,111 @@
+use std::env;
 use std::iter::zip;
 
-use itertools::Itertools;
+use operator::{parse_operator, Add, Concat, Inverse, Multiply, Operator};
+
+mod operator;
 
 const DAY: u8 = 7;
 
+/// Usage: `day7 [explain] [OPERATOR...]`
+///
+/// With `explain`, prints a satisfying equation for each solvable line. If operators are given,
+/// e.g. `+ - ** ||2`, reports the total for those operators instead of parts 1 and 2.
 fn main() {
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    let explain = args.first().is_some_and(|arg| arg == "explain");
+    if explain {
+        args.remove(0);
+    }
     let input = aocutil::load_input(DAY);
     let data = parse_input(&input);
 
-    println!("Part 1: {}", sum_solutions(&data, &vec!["+", "*"]));
-    println!("Part 2: {}", sum_solutions(&data, &vec!["+", "*", "||"]));
+    if !args.is_empty() {
+        let custom: Vec<Box<dyn Operator>> = args.iter()
+            .map(|symbol| parse_operator(symbol).unwrap_or_else(|| panic!("Unknown operator '{symbol}'")))
+            .collect();
+        let operators: Vec<&dyn Operator> = custom.iter().map(|op| op.as_ref()).collect();
+        println!("Total: {}", sum_solutions(&data, &operators, explain));
+        return;
+    }
+
+    println!("Part 1: {}", sum_solutions(&data, &[&Add, &Multiply], explain));
+    println!("Part 2: {}", sum_solutions(&data, &[&Add, &Multiply, &Concat(10)], explain));
 }
 
-fn sum_solutions(data: &[(i64, Vec<i64>)], operators: &Vec<&str>) -> i64 {
+fn sum_solutions(data: &[(i64, Vec<i64>)], operators: &[&dyn Operator], explain: bool) -> i64 {
     data.iter()
-        .filter(|(target, values)| can_solve(*target, values, operators))
+        .filter(|(target, values)| match solve(*target, values, operators) {
+            Some(ops) => {
+                debug_assert_eq!(apply_ops(&ops, values), Some(*target));
+                if explain {
+                    println!("{}", format_equation(*target, values, &ops));
+                }
+                true
+            }
+            None => false,
+        })
         .map(|(target, _values)| target)
         .sum()
 }
 
-fn can_solve(target: i64, values: &[i64], operators: &[&str]) -> bool {
-    itertools::repeat_n(operators.iter(), values.len() - 1)
-        .multi_cartesian_product()
-        .any(|ops| apply_ops(ops, values) == target)
+/// Finds operators to place between `values`, evaluated left to right, that produce `target`.
+///
+/// Works backwards from the target, undoing the last operator with its inverse, e.g. `+` by
+/// subtraction, `*` by exact division and `||` by stripping the last value's digits from the end of
+/// the target. Branches where the operator cannot be undone are pruned immediately. Operators
+/// without an inverse fall back to trying every left operand going forwards. Arithmetic that
+/// overflows makes a branch unsatisfiable.
+fn solve<'a>(target: i64, values: &[i64], operators: &[&'a dyn Operator]) -> Option<Vec<&'a dyn Operator>> {
+    // If nothing can go negative, then neither can any intermediate target
+    let non_negative = values.iter().all(|&v| v >= 0) && operators.iter().all(|op| op.preserves_non_negative());
+    solve_backwards(target, values, operators, non_negative)
+}
+
+fn solve_backwards<'a>(target: i64, values: &[i64], operators: &[&'a dyn Operator], non_negative: bool) -> Option<Vec<&'a dyn Operator>> {
+    if non_negative && target < 0 {
+        return None;
+    }
+    let (&last, rest) = values.split_last()?;
+    if rest.is_empty() {
+        return (target == last).then(Vec::new);
+    }
+    operators.iter().find_map(|&op| {
+        let mut ops = match op.inverse(target, last) {
+            Inverse::Left(left) => solve_backwards(left, rest, operators, non_negative)?,
+            Inverse::AnyLeft => solve_forwards(rest[0], &rest[1..], operators, &|_| true)?,
+            Inverse::Unsatisfiable => return None,
+            Inverse::Unknown => solve_forwards(rest[0], &rest[1..], operators, &|left| op.apply(left, last) == Some(target))?,
+        };
+        ops.push(op);
+        Some(ops)
+    })
 }
 
-fn apply_ops(ops: Vec<&&str>, values: &[i64]) -> i64 {
+/// Tries every sequence of operators from left to right, returning the first whose result is
+/// accepted.
+fn solve_forwards<'a>(result: i64, values: &[i64], operators: &[&'a dyn Operator], accept: &dyn Fn(i64) -> bool) -> Option<Vec<&'a dyn Operator>> {
+    let Some((&next, rest)) = values.split_first() else {
+        return accept(result).then(Vec::new);
+    };
+    operators.iter().find_map(|&op| {
+        let mut ops = solve_forwards(op.apply(result, next)?, rest, operators, accept)?;
+        ops.insert(0, op);
+        Some(ops)
+    })
+}
+
+fn apply_ops(ops: &[&dyn Operator], values: &[i64]) -> Option<i64> {
     zip(ops, values[1..].iter())
-        .fold(values[0], |result, (op, value)| match *op {
-            "+" => result + value,
-            "*" => result * value,
-            "||" => result * 10i64.pow(value.ilog10() + 1) + value,
-            _ => panic!()
-        })
+        .try_fold(values[0], |result, (op, &value)| op.apply(result, value))
+}
+
+fn format_equation(target: i64, values: &[i64], ops: &[&dyn Operator]) -> String {
+    let mut equation = format!("{target} = {}", values[0]);
+    for (op, value) in zip(ops, &values[1..]) {
+        equation.push_str(&format!(" {} {value}", op.symbol()));
+    }
+    equation
 }
 
 fn parse_input(input: &str) -> Vec<(i64, Vec<i64>)> {
@@ -43,4 +116,51 @@ fn parse_input(input: &str) -> Vec<(i64, Vec<i64>)> {
             rest.split_whitespace().map(|v| v.parse::<i64>().unwrap()).collect()
         ))
         .collect()
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+
+    const PART1: [&dyn Operator; 2] = [&Add, &Multiply];
+    const PART2: [&dyn Operator; 3] = [&Add, &Multiply, &Concat(10)];
+
+    fn symbols(ops: Option<Vec<&dyn Operator>>) -> Option<Vec<String>> {
+        ops.map(|ops| ops.iter().map(|op| op.symbol()).collect())
+    }
+
+    #[test]
+    fn solves_individual_lines() {
+        assert_eq!(symbols(solve(190, &[10, 19], &PART1)), Some(vec!["*".to_string()]));
+        assert_eq!(format_equation(3267, &[81, 40, 27], &solve(3267, &[81, 40, 27], &PART1).unwrap()), "3267 = 81 * 40 + 27");
+        assert_eq!(symbols(solve(156, &[15, 6], &PART1)), None);
+        assert_eq!(format_equation(7290, &[6, 8, 6, 15], &solve(7290, &[6, 8, 6, 15], &PART2).unwrap()), "7290 = 6 * 8 || 6 * 15");
+        assert_eq!(format_equation(100, &[10, 0], &solve(100, &[10, 0], &PART2).unwrap()), "100 = 10 || 0");
+        assert_eq!(format_equation(0, &[7, 3, 0], &solve(0, &[7, 3, 0], &PART1).unwrap()), "0 = 7 + 3 * 0");
+    }
+
+    #[test]
+    fn solves_with_extra_operators() {
+        let operators: Vec<Box<dyn Operator>> = ["+", "-", "/", "**", "||2"].iter().map(|s| parse_operator(s).unwrap()).collect();
+        let operators: Vec<&dyn Operator> = operators.iter().map(|op| op.as_ref()).collect();
+        assert_eq!(format_equation(-3, &[2, 5], &solve(-3, &[2, 5], &operators).unwrap()), "-3 = 2 - 5");
+        assert_eq!(format_equation(3, &[7, 2], &solve(3, &[7, 2], &operators).unwrap()), "3 = 7 / 2");
+        assert_eq!(format_equation(64, &[2, 3, 2], &solve(64, &[2, 3, 2], &operators).unwrap()), "64 = 2 ** 3 ** 2");
+        assert_eq!(format_equation(11, &[2, 3], &solve(11, &[2, 3], &operators).unwrap()), "11 = 2 ||2 3");
+    }
+
+    #[test]
+    fn overflowing_branches_are_unsatisfiable() {
+        assert!(solve(i64::MAX, &[i64::MAX, 2], &PART2).is_none());
+        assert_eq!(format_equation(i64::MAX, &[i64::MAX, 0], &solve(i64::MAX, &[i64::MAX, 0], &PART1).unwrap()), format!("{} = {} + 0", i64::MAX, i64::MAX));
+    }
+
+    #[test]
+    fn sums_example() {
+        let data = parse_input(&fs::read_to_string("test_input.txt").unwrap());
+        assert_eq!(sum_solutions(&data, &PART1, false), 3749);
+        assert_eq!(sum_solutions(&data, &PART2, false), 11387);
+    }
+}
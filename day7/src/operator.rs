@@ -0,0 +1,177 @@
+/// The left operand that makes `left op right` equal a target, when working backwards.
+#[derive(Debug, PartialEq)]
+pub enum Inverse {
+    /// Exactly this left operand gives the target
+    Left(i64),
+    /// Every left operand gives the target, e.g. multiplying by zero to get zero
+    AnyLeft,
+    /// No left operand gives the target
+    Unsatisfiable,
+    /// The operator cannot be inverted, so left operands must be tried going forwards
+    Unknown,
+}
+
+pub trait Operator {
+    fn symbol(&self) -> String;
+
+    /// Evaluates `left op right`, or `None` if the result overflows or is undefined.
+    fn apply(&self, left: i64, right: i64) -> Option<i64>;
+
+    fn inverse(&self, _target: i64, _right: i64) -> Inverse {
+        Inverse::Unknown
+    }
+
+    /// True if non-negative operands always give a non-negative result. If this holds for every
+    /// operator in use, the solver can discard any negative intermediate target.
+    fn preserves_non_negative(&self) -> bool {
+        false
+    }
+}
+
+pub struct Add;
+pub struct Subtract;
+pub struct Multiply;
+/// Integer division, rounding towards zero
+pub struct Divide;
+pub struct Power;
+/// Appends the digits of the right operand to the left operand, in the given base
+pub struct Concat(pub i64);
+
+impl Operator for Add {
+    fn symbol(&self) -> String {
+        "+".to_string()
+    }
+    fn apply(&self, left: i64, right: i64) -> Option<i64> {
+        left.checked_add(right)
+    }
+    fn inverse(&self, target: i64, right: i64) -> Inverse {
+        target.checked_sub(right).map_or(Inverse::Unsatisfiable, Inverse::Left)
+    }
+    fn preserves_non_negative(&self) -> bool {
+        true
+    }
+}
+
+impl Operator for Subtract {
+    fn symbol(&self) -> String {
+        "-".to_string()
+    }
+    fn apply(&self, left: i64, right: i64) -> Option<i64> {
+        left.checked_sub(right)
+    }
+    fn inverse(&self, target: i64, right: i64) -> Inverse {
+        target.checked_add(right).map_or(Inverse::Unsatisfiable, Inverse::Left)
+    }
+}
+
+impl Operator for Multiply {
+    fn symbol(&self) -> String {
+        "*".to_string()
+    }
+    fn apply(&self, left: i64, right: i64) -> Option<i64> {
+        left.checked_mul(right)
+    }
+    fn inverse(&self, target: i64, right: i64) -> Inverse {
+        match (target, right) {
+            (0, 0) => Inverse::AnyLeft,
+            (_, 0) => Inverse::Unsatisfiable,
+            _ if target.checked_rem(right) == Some(0) => target.checked_div(right).map_or(Inverse::Unsatisfiable, Inverse::Left),
+            _ => Inverse::Unsatisfiable,
+        }
+    }
+    fn preserves_non_negative(&self) -> bool {
+        true
+    }
+}
+
+impl Operator for Divide {
+    fn symbol(&self) -> String {
+        "/".to_string()
+    }
+    fn apply(&self, left: i64, right: i64) -> Option<i64> {
+        left.checked_div(right)
+    }
+    fn preserves_non_negative(&self) -> bool {
+        true
+    }
+}
+
+impl Operator for Power {
+    fn symbol(&self) -> String {
+        "**".to_string()
+    }
+    fn apply(&self, left: i64, right: i64) -> Option<i64> {
+        left.checked_pow(u32::try_from(right).ok()?)
+    }
+    fn preserves_non_negative(&self) -> bool {
+        true
+    }
+}
+
+impl Concat {
+    /// The multiplier that shifts the left operand along to make room for the digits of `right`.
+    fn shift(&self, right: i64) -> Option<i64> {
+        let digits = right.checked_ilog(self.0).unwrap_or(0) + 1;
+        self.0.checked_pow(digits)
+    }
+}
+
+impl Operator for Concat {
+    fn symbol(&self) -> String {
+        if self.0 == 10 { "||".to_string() } else { format!("||{}", self.0) }
+    }
+    fn apply(&self, left: i64, right: i64) -> Option<i64> {
+        if left < 0 || right < 0 {
+            return None;
+        }
+        left.checked_mul(self.shift(right)?)?.checked_add(right)
+    }
+    fn inverse(&self, target: i64, right: i64) -> Inverse {
+        match self.shift(right) {
+            Some(shift) if right >= 0 && target >= right && (target - right) % shift == 0 => Inverse::Left((target - right) / shift),
+            _ => Inverse::Unsatisfiable,
+        }
+    }
+    fn preserves_non_negative(&self) -> bool {
+        true
+    }
+}
+
+/// Looks up an operator by its symbol, e.g. `+`, `-`, `*`, `/`, `**`, `||` or `||2` for binary
+/// concatenation.
+pub fn parse_operator(symbol: &str) -> Option<Box<dyn Operator>> {
+    match symbol {
+        "+" => Some(Box::new(Add)),
+        "-" => Some(Box::new(Subtract)),
+        "*" => Some(Box::new(Multiply)),
+        "/" => Some(Box::new(Divide)),
+        "**" => Some(Box::new(Power)),
+        "||" => Some(Box::new(Concat(10))),
+        _ => {
+            let base = symbol.strip_prefix("||")?.parse().ok()?;
+            (base >= 2).then(|| Box::new(Concat(base)) as Box<dyn Operator>)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn overflow_is_unsatisfiable() {
+        assert_eq!(Add.apply(i64::MAX, 1), None);
+        assert_eq!(Multiply.apply(i64::MAX / 2, 3), None);
+        assert_eq!(Concat(10).apply(i64::MAX / 10, 12), None);
+        assert_eq!(Power.apply(10, 19), None);
+        assert_eq!(Subtract.inverse(i64::MAX, 1), Inverse::Unsatisfiable);
+    }
+
+    #[test]
+    fn concatenates_in_other_bases() {
+        assert_eq!(Concat(10).apply(12, 0), Some(120));
+        assert_eq!(Concat(2).apply(0b101, 0b11), Some(0b10111));
+        assert_eq!(Concat(2).inverse(0b10111, 0b11), Inverse::Left(0b101));
+        assert_eq!(Concat(16).inverse(0x1234, 0x35), Inverse::Unsatisfiable);
+    }
+}
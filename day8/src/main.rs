@@ -3,6 +3,7 @@ use itertools::Itertools;
 use aocutil::coord;
 use aocutil::coord::Coord;
 use aocutil::grid::Grid;
+use aocutil::math::gcd;
 
 const DAY: u8 = 8;
 
@@ -37,9 +38,13 @@ fn locations_for_mast_pair_pt1(a: Coord, b: Coord, _grid: &Grid) -> Vec<Coord> {
 }
 
 fn locations_for_mast_pair_pt2(a: Coord, b: Coord, grid: &Grid) -> Vec<Coord> {
+    // Step by the smallest whole-cell offset along the line, which matters if the offset between
+    // the masts has a common factor
     let d = coord::sub(b, a);
+    let g = gcd(d.0, d.1);
+    let d = (d.0 / g, d.1 / g);
     (0..)
-        .map(|i| [coord::sub(a, coord::mul(d, i)), coord::add(b, coord::mul(d, i))])
+        .map(|i| [coord::sub(a, coord::mul(d, i)), coord::add(a, coord::mul(d, i))])
         .take_while(|cs| cs.iter().any(|&c| grid.contains_coord(c)))
         .flatten()
         .collect()
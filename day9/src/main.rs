@@ -1,80 +1,243 @@
-use itertools::Itertools;
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+use std::{env, fs};
+
+mod render;
 
 const DAY: u8 = 9;
 
-#[derive(Clone, Copy)]
+/// Largest length of a file or free span, since lengths are single digits in the disk map.
+const MAX_LENGTH: u32 = 9;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
 enum Blocks {
     Free { length: u32 },
     File { id: usize, length: u32 },
 }
 
+/// Some or all of the blocks of a file moving from one position on the disk to another.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Move {
+    id: usize,
+    from: u32,
+    to: u32,
+    length: u32,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Strategy {
+    /// Part 1: move blocks one at a time from the end of the disk into the leftmost free block
+    BlockByBlock,
+    /// Part 2: move whole files into the leftmost free span that will hold them
+    FirstFit,
+    /// Move whole files into the shortest free span that will hold them
+    BestFit,
+    /// Move whole files into the longest free span that will hold them
+    WorstFit,
+    /// Slide every file left to close all gaps, keeping the files in their current order
+    Contiguous,
+}
+
+const STRATEGIES: [(&str, Strategy); 5] = [
+    ("block", Strategy::BlockByBlock),
+    ("first-fit", Strategy::FirstFit),
+    ("best-fit", Strategy::BestFit),
+    ("worst-fit", Strategy::WorstFit),
+    ("contiguous", Strategy::Contiguous),
+];
+
+struct Compaction {
+    blocks: Vec<Blocks>,
+    moves: Vec<Move>,
+}
+
+/// Usage: `day9 [compare | steps STRATEGY] [FILE]`
+///
+/// `compare` reports the moves and resulting fragmentation of every compaction strategy. `steps`
+/// prints the disk after each move made by one strategy: `block`, `first-fit`, `best-fit`,
+/// `worst-fit` or `contiguous`. `FILE` defaults to this day's `input.txt`.
 fn main() {
-    let input = aocutil::load_input(DAY);
-    let fs = parse_input(&input);
+    let args: Vec<String> = env::args().skip(1).collect();
+    let (command, strategy, filename) = match args.iter().map(String::as_str).collect::<Vec<_>>()[..] {
+        ["compare", ref rest @ ..] => ("compare", None, rest.first().copied()),
+        ["steps", name, ref rest @ ..] => {
+            let &(_, strategy) = STRATEGIES.iter().find(|(n, _)| *n == name)
+                .unwrap_or_else(|| panic!("Unknown strategy '{name}'"));
+            ("steps", Some(strategy), rest.first().copied())
+        }
+        ref rest => ("", None, rest.first().copied()),
+    };
+    let input = match filename {
+        Some(filename) => fs::read_to_string(filename)
+            .unwrap_or_else(|e| panic!("Unable to read file {}: {}", filename, e)),
+        None => aocutil::load_input(DAY),
+    };
+    let fs = parse_input(input.trim_end());
 
-    println!("Part 1: {}", checksum(&compact_part1(&fs)));
-    println!("Part 2: {}", checksum(&compact_part2(&fs)));
+    match command {
+        "compare" => {
+            println!("{:<12} {:>8} {:>10} {:>16} {:>16}", "strategy", "moves", "free gaps", "fragmented files", "checksum");
+            for (name, strategy) in STRATEGIES {
+                let compaction = compact(&fs, strategy);
+                let fragmentation = render::fragmentation(&compaction.blocks);
+                println!("{:<12} {:>8} {:>10} {:>16} {:>16}", name, compaction.moves.len(),
+                         fragmentation.free_gaps, fragmentation.fragmented_files, checksum(&compaction.blocks));
+            }
+        }
+        "steps" => {
+            let compaction = compact(&fs, strategy.unwrap());
+            let mut disk = render::expand(&fs);
+            println!("{}", render::render(&fs));
+            for m in compaction.moves {
+                render::apply_move(&mut disk, m);
+                println!("{}", render::render(&render::collapse(&disk)));
+            }
+        }
+        _ => {
+            println!("Part 1: {}", checksum(&compact(&fs, Strategy::BlockByBlock).blocks));
+            println!("Part 2: {}", checksum(&compact(&fs, Strategy::FirstFit).blocks));
+        }
+    }
+}
+
+fn compact(fs: &[Blocks], strategy: Strategy) -> Compaction {
+    match strategy {
+        Strategy::BlockByBlock => compact_block_by_block(fs),
+        // Each candidate is the leftmost free span of a given length: (start, length)
+        Strategy::FirstFit => compact_whole_files(fs, |candidates| candidates.iter().min().copied()),
+        Strategy::BestFit => compact_whole_files(fs, |candidates| candidates.iter().min_by_key(|&&(start, length)| (length, start)).copied()),
+        Strategy::WorstFit => compact_whole_files(fs, |candidates| candidates.iter().min_by_key(|&&(start, length)| (Reverse(length), start)).copied()),
+        Strategy::Contiguous => compact_contiguous(fs),
+    }
 }
 
-fn compact_part1(fs: &Vec<Blocks>) -> Vec<Blocks> {
-    let mut compacted = fs.clone();
+/// Moves file blocks one at a time from the end of the disk into the leftmost free space.
+fn compact_block_by_block(fs: &[Blocks]) -> Compaction {
+    let mut remaining = fs.to_vec();
+    let starts = block_starts(fs);
+    let mut compacted = vec![];
+    let mut moves = vec![];
+    let mut position = 0;
+    let mut end = remaining.len();
     let mut i = 0;
-    while i < compacted.len() {
-        if let Blocks::Free { length: free_length } = compacted[i] {
-            if let Some(Blocks::File { id: file_id, length: file_length }) = compacted.pop() {
-                if file_length >= free_length {
-                    // move some of the file into free space; keep remainder at the end of the file system
-                    compacted[i] = Blocks::File { id: file_id, length: free_length };
-                    if file_length != free_length {
-                        compacted.push(Blocks::File { id: file_id, length: file_length - free_length })
+    while i < end {
+        match remaining[i] {
+            Blocks::File { length: 0, .. } => {}
+            Blocks::File { length, .. } => {
+                compacted.push(remaining[i]);
+                position += length;
+            }
+            Blocks::Free { length } => {
+                let mut free_length = length;
+                while free_length > 0 {
+                    // Find the last file that still has blocks left to move
+                    while end > i + 1 && !matches!(remaining[end - 1], Blocks::File { length, .. } if length > 0) {
+                        end -= 1;
+                    }
+                    if end <= i + 1 {
+                        break;
                     }
-                } else if file_length > 0 {
-                    // move whole file into free space and reduce free space
-                    compacted[i] = Blocks::File { id: file_id, length: file_length };
-                    compacted.insert(i + 1, Blocks::Free { length: free_length - file_length })
+                    let Blocks::File { id, length: file_length } = remaining[end - 1] else { unreachable!() };
+                    let moved = file_length.min(free_length);
+                    compacted.push(Blocks::File { id, length: moved });
+                    remaining[end - 1] = Blocks::File { id, length: file_length - moved };
+                    moves.push(Move { id, from: starts[end - 1] + file_length - moved, to: position, length: moved });
+                    position += moved;
+                    free_length -= moved;
+                }
+                if free_length > 0 {
+                    compacted.push(Blocks::Free { length: free_length });
+                    position += free_length;
                 }
             }
-        } else {
-            i += 1;
         }
+        i += 1;
     }
-    compacted
+    Compaction { blocks: compacted, moves }
 }
 
-fn compact_part2(fs: &[Blocks]) -> Vec<Blocks> {
-    let mut compacted = fs.iter().cloned().collect_vec();
-    for &file in fs.iter().rev() {
-        if let Blocks::File { id: file_id, length: file_length } = file {
-            if let Some((free_index, free_length)) = find_first_free(&compacted, file_length) {
-                let file_index = find_file_index_by_id(&compacted, file_id);
-                if free_index < file_index {
-                    compacted[file_index] = Blocks::Free { length: file_length };
-                    compacted[free_index] = Blocks::File { id: file_id, length: file_length };
-                    if free_length > file_length {
-                        compacted.insert(free_index + 1, Blocks::Free { length: free_length - file_length });
-                    }
-                }
+/// Moves whole files, highest id first, into a free span to their left chosen by `choose`.
+///
+/// Free spans are kept in a min-heap of start positions for each span length, so the candidates
+/// passed to `choose` are just the leftmost span of each length that is long enough to hold the
+/// file. Space freed by a moving file is never reused because every file still to move starts
+/// further left.
+fn compact_whole_files(fs: &[Blocks], choose: impl Fn(&[(u32, u32)]) -> Option<(u32, u32)>) -> Compaction {
+    let mut free_spans: [BinaryHeap<Reverse<u32>>; MAX_LENGTH as usize + 1] = Default::default();
+    let mut files = vec![];
+    for (&block, start) in fs.iter().zip(block_starts(fs)) {
+        match block {
+            Blocks::Free { length } => if length > 0 {
+                free_spans[length as usize].push(Reverse(start));
             }
+            Blocks::File { id, length } => files.push((start, id, length)),
         }
     }
-    compacted
+
+    let mut moves = vec![];
+    for file in files.iter_mut().rev() {
+        let (file_position, id, file_length) = *file;
+        let candidates: Vec<(u32, u32)> = (file_length.max(1)..=MAX_LENGTH)
+            .filter_map(|length| free_spans[length as usize].peek().map(|&Reverse(start)| (start, length)))
+            .filter(|&(start, _)| start < file_position)
+            .collect();
+        if let Some((free_position, free_length)) = choose(&candidates) {
+            free_spans[free_length as usize].pop();
+            if free_length > file_length {
+                free_spans[(free_length - file_length) as usize].push(Reverse(free_position + file_length));
+            }
+            file.0 = free_position;
+            moves.push(Move { id, from: file_position, to: free_position, length: file_length });
+        }
+    }
+
+    files.sort_unstable();
+    Compaction { blocks: blocks_from_files(&files), moves }
+}
+
+fn compact_contiguous(fs: &[Blocks]) -> Compaction {
+    let mut moves = vec![];
+    let mut files = vec![];
+    let mut position = 0;
+    for (&block, start) in fs.iter().zip(block_starts(fs)) {
+        if let Blocks::File { id, length } = block {
+            if start != position {
+                moves.push(Move { id, from: start, to: position, length });
+            }
+            files.push((position, id, length));
+            position += length;
+        }
+    }
+    Compaction { blocks: blocks_from_files(&files), moves }
 }
 
-fn find_file_index_by_id(compacted: &[Blocks], file_id: usize) -> usize {
-    compacted.iter().find_position(|f| match f {
-        Blocks::Free { .. } => false,
-        Blocks::File { id, .. } => *id == file_id
-    }).unwrap().0
+fn block_starts(fs: &[Blocks]) -> Vec<u32> {
+    fs.iter()
+        .scan(0, |position, block| {
+            let start = *position;
+            *position += match block {
+                Blocks::Free { length } | Blocks::File { length, .. } => length,
+            };
+            Some(start)
+        })
+        .collect()
 }
 
-fn find_first_free(fs: &[Blocks], min_length: u32) -> Option<(usize, u32)> {
-    fs.iter().enumerate().find_map(|(i, b)| match b {
-        Blocks::Free { length } => if *length >= min_length { Some((i, *length)) } else { None },
-        Blocks::File { .. } => None,
-    })
+/// Lays out files sorted by position, with free space in the gaps between them.
+fn blocks_from_files(files: &[(u32, usize, u32)]) -> Vec<Blocks> {
+    let mut blocks = vec![];
+    let mut position = 0;
+    for &(file_position, id, length) in files {
+        if file_position > position {
+            blocks.push(Blocks::Free { length: file_position - position });
+        }
+        blocks.push(Blocks::File { id, length });
+        position = file_position + length;
+    }
+    blocks
 }
 
-fn checksum(blocks: &Vec<Blocks>) -> usize {
+fn checksum(blocks: &[Blocks]) -> usize {
     let mut total: usize = 0;
     let mut i = 0;
     for block in blocks {
@@ -101,4 +264,53 @@ fn parse_input(input: &str) -> Vec<Blocks> {
                 Blocks::Free { length }
             })
         .collect()
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn compacts_example() {
+        let fs = parse_input("2333133121414131402");
+        assert_eq!(checksum(&compact(&fs, Strategy::BlockByBlock).blocks), 1928);
+        assert_eq!(checksum(&compact(&fs, Strategy::FirstFit).blocks), 2858);
+    }
+
+    #[test]
+    fn leaves_files_that_do_not_fit_to_their_left() {
+        // 0..111....22222 becomes 022111222...... in part 1, but no whole file can move in part 2
+        let fs = parse_input("12345");
+        assert_eq!(checksum(&compact(&fs, Strategy::BlockByBlock).blocks), 60);
+        assert_eq!(checksum(&compact(&fs, Strategy::FirstFit).blocks), 132);
+    }
+
+    #[test]
+    fn moves_replay_to_compacted_disk() {
+        let fs = parse_input("2333133121414131402");
+        for (_, strategy) in STRATEGIES {
+            let compaction = compact(&fs, strategy);
+            let mut disk = render::expand(&fs);
+            compaction.moves.iter().for_each(|&m| render::apply_move(&mut disk, m));
+            assert_eq!(render::render(&render::collapse(&disk)).trim_end_matches('.'),
+                       render::render(&compaction.blocks).trim_end_matches('.'), "{strategy:?}");
+        }
+    }
+
+    #[test]
+    fn compares_strategies() {
+        // 0..1.....2.3
+        let fs = parse_input("1215111");
+        let results: Vec<(String, usize)> = STRATEGIES.iter()
+            .map(|&(_, strategy)| compact(&fs, strategy))
+            .map(|compaction| (render::render(&compaction.blocks), compaction.moves.len()))
+            .collect();
+        assert_eq!(results, vec![
+            ("0321.....".to_string(), 2),
+            ("0321".to_string(), 2),
+            ("021.......3".to_string(), 3),
+            ("01..32".to_string(), 3),
+            ("0123".to_string(), 3),
+        ]);
+    }
+}
@@ -0,0 +1,118 @@
+use std::collections::HashMap;
+
+use crate::{Blocks, Move};
+
+/// Disks longer than this, or with file ids of more than one digit, are rendered in compact form.
+const MAX_FULL_LENGTH: usize = 100;
+
+pub struct Fragmentation {
+    /// Number of free spans that have file blocks after them
+    pub free_gaps: usize,
+    /// Number of files whose blocks are not all contiguous
+    pub fragmented_files: usize,
+}
+
+/// One entry per disk block: the id of the file occupying it, or `None` if free.
+pub fn expand(blocks: &[Blocks]) -> Vec<Option<usize>> {
+    blocks.iter()
+        .flat_map(|&block| match block {
+            Blocks::Free { length } => std::iter::repeat_n(None, length as usize),
+            Blocks::File { id, length } => std::iter::repeat_n(Some(id), length as usize),
+        })
+        .collect()
+}
+
+/// Merges runs of identical disk blocks back into `Blocks`.
+pub fn collapse(disk: &[Option<usize>]) -> Vec<Blocks> {
+    disk.chunk_by(|a, b| a == b)
+        .map(|run| match run[0] {
+            None => Blocks::Free { length: run.len() as u32 },
+            Some(id) => Blocks::File { id, length: run.len() as u32 },
+        })
+        .collect()
+}
+
+pub fn apply_move(disk: &mut [Option<usize>], m: Move) {
+    let (from, to, length) = (m.from as usize, m.to as usize, m.length as usize);
+    assert!(disk[from..from + length].iter().all(|&block| block == Some(m.id)), "Move {m:?} does not start from file blocks");
+    disk[from..from + length].fill(None);
+    assert!(disk[to..to + length].iter().all(|block| block.is_none()), "Move {m:?} does not end in free blocks");
+    disk[to..to + length].fill(Some(m.id));
+}
+
+/// Renders in the puzzle's notation, e.g. `00...111...2`, unless the disk is too long or has file
+/// ids that are not single digits, in which case the compact notation is used.
+pub fn render(blocks: &[Blocks]) -> String {
+    let length: usize = blocks.iter().map(|&block| block_length(block) as usize).sum();
+    let single_digit_ids = blocks.iter().all(|block| !matches!(block, Blocks::File { id, .. } if *id >= 10));
+    if length <= MAX_FULL_LENGTH && single_digit_ids {
+        render_full(blocks)
+    } else {
+        render_compact(blocks)
+    }
+}
+
+pub fn render_full(blocks: &[Blocks]) -> String {
+    expand(blocks).iter()
+        .map(|block| match block {
+            None => '.',
+            Some(id) => char::from_digit((id % 10) as u32, 10).unwrap(),
+        })
+        .collect()
+}
+
+/// Renders each run of blocks as `id:length`, or `.:length` for free space, e.g. `0:2 .:3 1:3`.
+pub fn render_compact(blocks: &[Blocks]) -> String {
+    collapse(&expand(blocks)).iter()
+        .map(|&block| match block {
+            Blocks::Free { length } => format!(".:{length}"),
+            Blocks::File { id, length } => format!("{id}:{length}"),
+        })
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+pub fn fragmentation(blocks: &[Blocks]) -> Fragmentation {
+    let runs = collapse(&expand(blocks));
+    let last_file = runs.iter().rposition(|block| matches!(block, Blocks::File { .. })).unwrap_or(0);
+    let free_gaps = runs[..last_file].iter().filter(|block| matches!(block, Blocks::Free { .. })).count();
+
+    let mut extents: HashMap<usize, usize> = HashMap::new();
+    for &block in &runs {
+        if let Blocks::File { id, .. } = block {
+            *extents.entry(id).or_default() += 1;
+        }
+    }
+    let fragmented_files = extents.values().filter(|&&count| count > 1).count();
+
+    Fragmentation { free_gaps, fragmented_files }
+}
+
+fn block_length(block: Blocks) -> u32 {
+    match block {
+        Blocks::Free { length } | Blocks::File { length, .. } => length,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{compact, parse_input, Strategy};
+
+    #[test]
+    fn renders_example() {
+        let fs = parse_input("2333133121414131402");
+        assert_eq!(render(&fs), "00...111...2...333.44.5555.6666.777.888899");
+        assert_eq!(render(&compact(&fs, Strategy::FirstFit).blocks), "00992111777.44.333....5555.6666.....8888");
+        assert_eq!(render_compact(&parse_input("23331")), "0:2 .:3 1:3 .:3 2:1");
+    }
+
+    #[test]
+    fn measures_fragmentation() {
+        let fs = parse_input("2333133121414131402");
+        let block_by_block = fragmentation(&compact(&fs, Strategy::BlockByBlock).blocks);
+        assert_eq!((block_by_block.free_gaps, block_by_block.fragmented_files), (0, 2));
+        let first_fit = fragmentation(&compact(&fs, Strategy::FirstFit).blocks);
+        assert_eq!((first_fit.free_gaps, first_fit.fragmented_files), (5, 0));
+    }
+}